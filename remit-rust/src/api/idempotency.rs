@@ -0,0 +1,89 @@
+use actix_web::{http::StatusCode, HttpRequest, HttpResponse};
+use hex::encode as hex_encode;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::errors::{AppError, AppResult};
+use crate::repositories::{IdempotencyOutcome, IdempotencyRepository};
+
+/// Outcome of checking an `Idempotency-Key` header against a prior attempt
+pub enum IdempotencyCheck {
+    /// No header was supplied, or this is the first use of the key: the handler should run as
+    /// normal and call `complete_idempotency` with `key` once it has a response
+    Proceed { key: String },
+    /// The same key and request body already completed; replay the stored response verbatim
+    Replay(HttpResponse),
+}
+
+/// Hash a request body for idempotency comparison
+fn hash_body(body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(body);
+    hex_encode(hasher.finalize())
+}
+
+/// Claim the `Idempotency-Key` header on `req` against `repo`, if present.
+///
+/// Returns `Proceed` when there's no header or this is the first use of the key. Returns
+/// `Replay` with the original response when the same key and body already completed. Errors
+/// with `AppError::conflict` if the original request with this key is still in flight, or
+/// `AppError::idempotency_conflict` if the key was reused with a different request body.
+pub async fn check_idempotency(req: &HttpRequest, repo: &IdempotencyRepository, body: &[u8]) -> AppResult<IdempotencyCheck> {
+    let key = match req.headers().get("Idempotency-Key").and_then(|v| v.to_str().ok()) {
+        Some(key) if !key.is_empty() => key.to_string(),
+        _ => return Ok(IdempotencyCheck::Proceed { key: String::new() }),
+    };
+
+    let request_hash = hash_body(body);
+
+    match repo.begin(&key, &request_hash).await? {
+        IdempotencyOutcome::Started => Ok(IdempotencyCheck::Proceed { key }),
+        IdempotencyOutcome::Completed { response_status, response_body } => {
+            let status = StatusCode::from_u16(response_status).unwrap_or(StatusCode::OK);
+            Ok(IdempotencyCheck::Replay(
+                HttpResponse::build(status).content_type("application/json").body(response_body)
+            ))
+        }
+        IdempotencyOutcome::InProgress => Err(AppError::conflict(
+            "Original request with this Idempotency-Key is still in progress; retry later".to_string()
+        )),
+        IdempotencyOutcome::Conflict => Err(AppError::idempotency_conflict(
+            "Idempotency-Key was already used with a different request body".to_string()
+        )),
+    }
+}
+
+/// Release `key` after its handler failed, so a legitimate retry isn't stuck rejected with
+/// `AppError::conflict` as `InProgress` for the rest of `idempotency_retention_days`. A no-op
+/// when `key` is empty, i.e. no Idempotency-Key header was supplied on the original request.
+/// Best-effort: a failure to release is logged rather than propagated, so it doesn't mask the
+/// original error that triggered the release.
+pub async fn release_idempotency(repo: &IdempotencyRepository, key: &str) {
+    if key.is_empty() {
+        return;
+    }
+
+    if let Err(e) = repo.release(key).await {
+        tracing::warn!("Failed to release idempotency key {}: {}", key, e);
+    }
+}
+
+/// Record `value` as the response for `key` and return it as an `HttpResponse`, so a retried
+/// call with the same key replays this exact response instead of rerunning the handler. A
+/// no-op (aside from building the response) when `key` is empty, i.e. no Idempotency-Key header
+/// was supplied on the original request.
+pub async fn complete_idempotency<T: Serialize>(
+    repo: &IdempotencyRepository,
+    key: &str,
+    status: StatusCode,
+    value: &T,
+) -> AppResult<HttpResponse> {
+    let body = serde_json::to_string(value)
+        .map_err(|e| AppError::internal_error(format!("Failed to serialize response: {}", e)))?;
+
+    if !key.is_empty() {
+        repo.complete(key, status.as_u16(), &body).await?;
+    }
+
+    Ok(HttpResponse::build(status).content_type("application/json").body(body))
+}
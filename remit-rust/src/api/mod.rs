@@ -1,5 +1,6 @@
 pub mod remittance;
 pub mod webhooks;
+pub mod idempotency;
 
 pub fn configure(cfg: &mut actix_web::web::ServiceConfig) {
     remittance::configure(cfg);
@@ -1,4 +1,5 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
 use paperclip::actix::{
     api_v2_operation,
     web::{Json, Path, Query},
@@ -7,9 +8,11 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::api::idempotency::{check_idempotency, complete_idempotency, release_idempotency, IdempotencyCheck};
 use crate::errors::{AppError, AppResult};
-use crate::models::{Transaction, TransactionStatus, BankAccountDetails};
-use crate::services::RemittanceService;
+use crate::models::{Transaction, TransactionStatus, BankAccountDetails, Quote, UserLedgerEntry};
+use crate::repositories::IdempotencyRepository;
+use crate::services::{ReconciliationService, RemittanceService, SettlementEntry};
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreateTransactionRequest {
@@ -24,6 +27,18 @@ pub struct CreateTransactionRequest {
     
     #[validate(length(min = 0, max = 500))]
     pub notes: Option<String>,
+
+    /// Client-supplied key so a retried POST returns the original transaction instead of a duplicate
+    pub idempotency_key: Option<String>,
+
+    /// ID of a previously-created `Quote` to lock this transaction's rate and fee to
+    pub quote_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateQuoteRequest {
+    #[validate(range(min = 1000, max = 1_000_000))]
+    pub source_amount: Decimal,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,6 +54,12 @@ pub struct EstimateExchangeResponse {
     pub destination_currency: String,
     pub exchange_rate: Decimal,
     pub fee: Decimal,
+
+    /// ID of the `Quote` this estimate was locked to; pass it as `create_transaction`'s
+    /// `quote_id` to bind the transaction to this exact rate and fee.
+    pub quote_id: String,
+
+    pub expires_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -51,6 +72,11 @@ pub struct InitiatePaymentResponse {
 pub struct TransactionListResponse {
     pub transactions: Vec<Transaction>,
     pub total: usize,
+
+    /// Last item's `row_id`, to pass back as `start` on the next `/history` call; `None` when
+    /// the page came back empty (nothing new, or the long poll timed out). Always `None` from
+    /// `/user/{user_id}`, which does not paginate.
+    pub next_cursor: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -60,6 +86,36 @@ pub struct TransactionListQuery {
     pub status: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserBalanceQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserBalanceResponse {
+    pub user_id: String,
+    pub currency: String,
+    pub balance: Decimal,
+    pub history: Vec<UserLedgerEntry>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionHistoryQuery {
+    /// Opaque monotonic row cursor (a transaction's `row_id`) to read from; omit to start from
+    /// the newest (`delta < 0`) or oldest (`delta > 0`) row.
+    pub start: Option<i64>,
+
+    /// Signed page size: positive returns up to `delta` rows after `start` ascending, negative
+    /// returns up to `|delta|` rows before `start` descending.
+    pub delta: i64,
+
+    /// How long to wait for a new row before returning an empty page, when `delta` is positive
+    /// and nothing matches yet. Ignored for `delta <= 0`.
+    pub long_poll_ms: Option<u64>,
+}
+
 /// Create a new remittance transaction
 #[api_v2_operation(
     summary = "Create a new remittance transaction",
@@ -69,22 +125,61 @@ pub struct TransactionListQuery {
     tags(name = "Remittance"),
 )]
 pub async fn create_transaction(
+    req: HttpRequest,
     service: web::Data<RemittanceService>,
+    idempotency_repo: web::Data<IdempotencyRepository>,
     Json(request): Json<CreateTransactionRequest>,
 ) -> AppResult<HttpResponse> {
     // Validate request
     request.validate()
         .map_err(|e| AppError::validation_error(format!("Invalid request: {}", e)))?;
-    
-    // Create transaction
-    let transaction = service.create_transaction(
+
+    // Claim the Idempotency-Key header, if present, before running any side effects, and
+    // replay the original response verbatim if this exact key+body already completed
+    let request_hash_body = serde_json::to_vec(&request).unwrap_or_default();
+    let idempotency_key = match check_idempotency(&req, &idempotency_repo, &request_hash_body).await? {
+        IdempotencyCheck::Replay(response) => return Ok(response),
+        IdempotencyCheck::Proceed { key } => key,
+    };
+
+    // Create transaction, releasing the claimed Idempotency-Key on failure so a legitimate
+    // retry isn't rejected as still-in-progress for the rest of the claim's retention window
+    let transaction = match service.create_transaction(
         request.user_id,
         request.source_amount,
         request.recipient_id,
         request.notes,
-    ).await?;
-    
-    Ok(HttpResponse::Created().json(transaction))
+        request.idempotency_key,
+        request.quote_id,
+    ).await {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            release_idempotency(&idempotency_repo, &idempotency_key).await;
+            return Err(e);
+        }
+    };
+
+    complete_idempotency(&idempotency_repo, &idempotency_key, StatusCode::CREATED, &transaction).await
+}
+
+/// Create a locked-rate quote
+#[api_v2_operation(
+    summary = "Create a quote",
+    description = "Locks the current exchange rate and fee for a short validity window; pass the returned quote_id to create_transaction to bind a transaction to it",
+    consumes = "application/json",
+    produces = "application/json",
+    tags(name = "Remittance"),
+)]
+pub async fn create_quote(
+    service: web::Data<RemittanceService>,
+    Json(request): Json<CreateQuoteRequest>,
+) -> AppResult<HttpResponse> {
+    request.validate()
+        .map_err(|e| AppError::validation_error(format!("Invalid request: {}", e)))?;
+
+    let quote: Quote = service.create_quote(request.source_amount, "INR", "CAD").await?;
+
+    Ok(HttpResponse::Created().json(quote))
 }
 
 /// Get a transaction by ID
@@ -118,15 +213,99 @@ pub async fn get_user_transactions(
 ) -> AppResult<HttpResponse> {
     let limit = query.limit.map(|l| l as i32).or(Some(50));
     let transactions = service.get_user_transactions(&user_id, limit).await?;
-    
+
     let response = TransactionListResponse {
         total: transactions.len(),
         transactions,
+        next_cursor: None,
     };
-    
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Get a cursor-paginated, long-polling page of a user's transaction history
+#[api_v2_operation(
+    summary = "Get user transaction history",
+    description = "Reads a page of a user's transactions after/before an opaque row cursor, long-polling for a new row when delta is positive and none currently exist",
+    consumes = "application/json",
+    produces = "application/json",
+    tags(name = "Remittance"),
+)]
+pub async fn get_user_transaction_history(
+    service: web::Data<RemittanceService>,
+    Path(user_id): Path<String>,
+    Query(query): Query<TransactionHistoryQuery>,
+) -> AppResult<HttpResponse> {
+    if query.delta == 0 {
+        return Err(AppError::validation_error("delta must be non-zero".to_string()));
+    }
+
+    let long_poll_ms = query.long_poll_ms.unwrap_or(0).min(60_000);
+    let transactions = service
+        .get_user_transaction_history(&user_id, query.start, query.delta, long_poll_ms)
+        .await?;
+
+    let next_cursor = transactions.last().map(|t| t.row_id);
+    let response = TransactionListResponse {
+        total: transactions.len(),
+        transactions,
+        next_cursor,
+    };
+
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Get a user's prepaid balance and a page of their deposit/debit history
+#[api_v2_operation(
+    summary = "Get user balance",
+    description = "Gets a user's current prepaid balance plus a paginated history of deposits and debits",
+    consumes = "application/json",
+    produces = "application/json",
+    tags(name = "Remittance"),
+)]
+pub async fn get_user_balance(
+    service: web::Data<RemittanceService>,
+    Path(user_id): Path<String>,
+    Query(query): Query<UserBalanceQuery>,
+) -> AppResult<HttpResponse> {
+    let limit = query.limit.map(|l| l as i32);
+    let (balance, history) = service
+        .get_user_balance(&user_id, limit, query.cursor.as_deref())
+        .await?;
+
+    let response = UserBalanceResponse {
+        user_id,
+        currency: "INR".to_string(),
+        balance,
+        history: history.items,
+        next_cursor: history.next_cursor,
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReconcileRequest {
+    pub entries: Vec<SettlementEntry>,
+}
+
+/// Reconcile a bank/payout-provider settlement file against outstanding transactions
+#[api_v2_operation(
+    summary = "Reconcile settlement file",
+    description = "Admin endpoint: matches a batch of settlement entries against pending/transferred \
+                    transactions and updates their statuses in bulk",
+    consumes = "application/json",
+    produces = "application/json",
+    tags(name = "Remittance"),
+)]
+pub async fn reconcile_settlement(
+    service: web::Data<ReconciliationService>,
+    Json(request): Json<ReconcileRequest>,
+) -> AppResult<HttpResponse> {
+    let outcome = service.reconcile(request.entries).await?;
+    Ok(HttpResponse::Ok().json(outcome))
+}
+
 /// Initiate payment for a transaction
 #[api_v2_operation(
     summary = "Initiate payment",
@@ -136,17 +315,34 @@ pub async fn get_user_transactions(
     tags(name = "Remittance"),
 )]
 pub async fn initiate_payment(
+    req: HttpRequest,
     service: web::Data<RemittanceService>,
+    idempotency_repo: web::Data<IdempotencyRepository>,
     Path(transaction_id): Path<String>,
 ) -> AppResult<HttpResponse> {
-    let payment_link = service.initiate_payment(&transaction_id).await?;
-    
+    // Claim the Idempotency-Key header, if present, before initiating payment, and replay the
+    // original response verbatim if this exact key+transaction already completed
+    let idempotency_key = match check_idempotency(&req, &idempotency_repo, transaction_id.as_bytes()).await? {
+        IdempotencyCheck::Replay(response) => return Ok(response),
+        IdempotencyCheck::Proceed { key } => key,
+    };
+
+    // Releasing on failure so a legitimate retry isn't rejected as still-in-progress for the
+    // rest of the claim's retention window
+    let payment_link = match service.initiate_payment(&transaction_id).await {
+        Ok(payment_link) => payment_link,
+        Err(e) => {
+            release_idempotency(&idempotency_repo, &idempotency_key).await;
+            return Err(e);
+        }
+    };
+
     let response = InitiatePaymentResponse {
         transaction_id,
         payment_link,
     };
-    
-    Ok(HttpResponse::Ok().json(response))
+
+    complete_idempotency(&idempotency_repo, &idempotency_key, StatusCode::OK, &response).await
 }
 
 /// Estimate exchange rate and destination amount
@@ -162,18 +358,22 @@ pub async fn estimate_exchange(
     Json(request): Json<EstimateExchangeRequest>,
 ) -> AppResult<HttpResponse> {
     let source_amount = request.source_amount;
-    let fee = service.calculate_fee(source_amount);
-    let (destination_amount, exchange_rate) = service.calculate_destination_amount(source_amount).await?;
-    
+
+    // Persist the estimate as a locked-rate Quote, so the rate and fee the customer sees here
+    // are exactly what create_transaction will honor if they pass the returned quote_id back.
+    let quote = service.create_quote(source_amount, "INR", "CAD").await?;
+
     let response = EstimateExchangeResponse {
-        source_amount,
-        source_currency: "INR".to_string(),
-        destination_amount,
-        destination_currency: "CAD".to_string(),
-        exchange_rate,
-        fee,
+        source_amount: quote.source_amount,
+        source_currency: quote.source_currency.clone(),
+        destination_amount: quote.destination_amount,
+        destination_currency: quote.destination_currency.clone(),
+        exchange_rate: quote.exchange_rate,
+        fee: quote.fee,
+        quote_id: quote.quote_id,
+        expires_at: quote.expires_at,
     };
-    
+
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -214,10 +414,14 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/remittance")
             .route("", web::post().to(create_transaction))
+            .route("/quote", web::post().to(create_quote))
             .route("/estimate", web::post().to(estimate_exchange))
             .route("/{transaction_id}", web::get().to(get_transaction))
             .route("/{transaction_id}/payment", web::post().to(initiate_payment))
             .route("/{transaction_id}/status", web::get().to(check_transaction_status))
-            .route("/user/{user_id}", web::get().to(get_user_transactions)),
+            .route("/user/{user_id}", web::get().to(get_user_transactions))
+            .route("/user/{user_id}/balance", web::get().to(get_user_balance))
+            .route("/history/{user_id}", web::get().to(get_user_transaction_history))
+            .route("/reconcile", web::post().to(reconcile_settlement)),
     );
 } 
\ No newline at end of file
@@ -1,16 +1,162 @@
-use actix_web::{web, HttpResponse};
-use paperclip::actix::{
-    api_v2_operation,
-    web::Json,
-};
-use serde_json::to_string;
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest, HttpResponse};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use paperclip::actix::api_v2_operation;
+use secrecy::ExposeSecret;
+use sha2::Sha256;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
 
+use crate::config::{get_config, AppConfig};
 use crate::errors::{AppError, AppResult};
-use crate::integrations::{UpiWebhookPayload, WiseWebhookPayload};
+use crate::integrations::WiseWebhookPayload;
 use crate::models::TransactionStatus;
-use crate::repositories::TransactionRepository;
+use crate::repositories::{ProcessedEventFilter, TransactionRepository};
 use crate::services::RemittanceService;
 
+type HmacSha256 = Hmac<Sha256>;
+
+/// Derive a dedupe key for a single webhook event from its source and the provider's own event
+/// id, so a duplicate delivery (provider retry, at-least-once queue redelivery, or a repeat
+/// entry inside a batched payload) doesn't double-apply its side effects when claimed against
+/// `ProcessedEventFilter`. Keying on the event id rather than the whole body lets a provider
+/// that batches several events into one delivery be deduped per-event instead of all-or-nothing.
+fn event_key(source: &str, id: &str) -> String {
+    format!("{}:{}", source, id)
+}
+
+/// Verify a hex-encoded HMAC-SHA256 signature over `message`, in constant time, against any of
+/// the given candidate secrets (plural to support rotation: the current secret plus a still-valid
+/// previous one).
+fn verify_hmac_signature(message: &[u8], signature_hex: &str, secrets: &[&str]) -> bool {
+    let signature = match hex::decode(signature_hex) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+
+    secrets.iter().any(|secret| {
+        HmacSha256::new_from_slice(secret.as_bytes())
+            .map(|mut mac| {
+                mac.update(message);
+                mac.verify_slice(&signature).is_ok()
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// Read a required header as a `&str`, rejecting with `AppError::unauthorized` if it's
+/// missing or not valid UTF-8
+fn required_header<'a>(req: &'a HttpRequest, name: &str) -> AppResult<&'a str> {
+    req.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::unauthorized(format!("Missing or invalid {} header", name)))
+}
+
+/// Reject a webhook whose unix-seconds `timestamp` header has drifted more than
+/// `tolerance_secs` from now in either direction, so a captured-and-replayed request can't be
+/// replayed indefinitely even if its signature and event id are both still valid.
+fn verify_timestamp(timestamp: &str, tolerance_secs: u64) -> AppResult<()> {
+    let timestamp: i64 = timestamp.parse()
+        .map_err(|_| AppError::validation_error("Invalid timestamp header"))?;
+
+    if (Utc::now().timestamp() - timestamp).unsigned_abs() > tolerance_secs {
+        return Err(AppError::webhook_verification_error("Webhook timestamp outside tolerance window"));
+    }
+
+    Ok(())
+}
+
+/// Names the signature/timestamp headers and candidate HMAC secrets a webhook provider is
+/// verified against, so each provider's wiring lives in one small impl instead of being
+/// copy-pasted into every handler.
+trait WebhookSignature {
+    const SIGNATURE_HEADER: &'static str;
+    const TIMESTAMP_HEADER: &'static str;
+
+    /// Candidate secrets, current first, to try the signature against
+    fn secrets(config: &AppConfig) -> Vec<String>;
+}
+
+struct UpiWebhook;
+impl WebhookSignature for UpiWebhook {
+    const SIGNATURE_HEADER: &'static str = "X-Upi-Signature";
+    const TIMESTAMP_HEADER: &'static str = "X-Upi-Timestamp";
+
+    fn secrets(config: &AppConfig) -> Vec<String> {
+        std::iter::once(config.payment.upi_webhook_secret.expose_secret().clone())
+            .chain(config.payment.upi_webhook_secret_previous.as_ref().map(|s| s.expose_secret().clone()))
+            .collect()
+    }
+}
+
+struct WiseWebhook;
+impl WebhookSignature for WiseWebhook {
+    const SIGNATURE_HEADER: &'static str = "X-Wise-Signature";
+    const TIMESTAMP_HEADER: &'static str = "X-Wise-Timestamp";
+
+    fn secrets(config: &AppConfig) -> Vec<String> {
+        std::iter::once(config.transfer.wise_webhook_secret.clone())
+            .chain(config.transfer.wise_webhook_secret_previous.clone())
+            .collect()
+    }
+}
+
+struct DepositWebhook;
+impl WebhookSignature for DepositWebhook {
+    const SIGNATURE_HEADER: &'static str = "X-Deposit-Signature";
+    const TIMESTAMP_HEADER: &'static str = "X-Deposit-Timestamp";
+
+    fn secrets(config: &AppConfig) -> Vec<String> {
+        std::iter::once(config.payment.deposit_webhook_secret.clone())
+            .chain(config.payment.deposit_webhook_secret_previous.clone())
+            .collect()
+    }
+}
+
+/// Extractor that verifies a `T: WebhookSignature` provider's HMAC-SHA256 signature over
+/// `timestamp + "." + raw_body` and rejects a stale timestamp, before a handler ever sees the
+/// body. Capturing the raw bytes here (rather than in each handler) and verifying before
+/// deserializing means a new webhook route can't be added without going through this check.
+pub struct VerifiedWebhook<T> {
+    pub body: web::Bytes,
+    _provider: PhantomData<T>,
+}
+
+impl<T: WebhookSignature> FromRequest for VerifiedWebhook<T> {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        let mut payload = payload.take();
+
+        Box::pin(async move {
+            let body = web::Bytes::from_request(&req, &mut payload).await
+                .map_err(|_| AppError::validation_error("Failed to read webhook request body"))?;
+
+            let signature = required_header(&req, T::SIGNATURE_HEADER)?;
+            let timestamp = required_header(&req, T::TIMESTAMP_HEADER)?;
+
+            let config = get_config();
+            verify_timestamp(timestamp, config.business_rules.webhook_timestamp_tolerance_seconds)?;
+
+            let mut message = timestamp.as_bytes().to_vec();
+            message.push(b'.');
+            message.extend_from_slice(&body);
+
+            let secrets = T::secrets(&config);
+            let secret_refs: Vec<&str> = secrets.iter().map(String::as_str).collect();
+            if !verify_hmac_signature(&message, signature, &secret_refs) {
+                return Err(AppError::webhook_verification_error(format!("Invalid {} signature", T::SIGNATURE_HEADER)));
+            }
+
+            Ok(VerifiedWebhook { body, _provider: PhantomData })
+        })
+    }
+}
+
 /// Process UPI payment webhook
 #[api_v2_operation(
     summary = "UPI Payment Webhook",
@@ -22,29 +168,37 @@ use crate::services::RemittanceService;
 pub async fn upi_webhook(
     service: web::Data<RemittanceService>,
     repo: web::Data<TransactionRepository>,
-    Json(payload): Json<UpiWebhookPayload>,
+    processed_events: web::Data<ProcessedEventFilter>,
+    webhook: VerifiedWebhook<UpiWebhook>,
 ) -> AppResult<HttpResponse> {
-    // Find transaction by reference_id
-    let reference_id = payload.reference_id.clone();
-    
-    // Query transactions by reference_id is not directly supported,
-    // so we need to get all transactions and filter
-    let transactions = repo.get_by_status(TransactionStatus::Pending, None).await?;
-    
-    let transaction = transactions.into_iter()
-        .find(|t| {
-            t.payment_details.reference_id.as_ref().map_or(false, |r| r == &reference_id)
-        })
+    let payload: serde_json::Value = serde_json::from_slice(&webhook.body)
+        .map_err(|e| AppError::validation_error(format!("Invalid webhook payload: {}", e)))?;
+
+    // Find transaction by reference_id, via the PaymentReferenceIdIndex
+    let reference_id = payload.get("reference_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| AppError::validation_error("Missing reference_id in webhook payload"))?
+        .to_string();
+    let status = payload.get("status").and_then(|v| v.as_str()).unwrap_or("unknown");
+
+    // Dedupe this specific event (reference_id + status) before applying any side effects,
+    // so a redelivery or a repeat entry in a batched payload is skipped independently of others
+    if !processed_events.try_claim(&event_key("upi", &format!("{}:{}", reference_id, status))).await? {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "Payment webhook already processed"
+        })));
+    }
+
+    let transaction = repo.get_by_payment_reference_id(&reference_id).await?
         .ok_or_else(|| AppError::not_found(format!("Transaction not found for reference_id: {}", reference_id)))?;
-    
-    // Process the UPI payment
-    let payment_details = service
-        .upi_client
-        .process_webhook(payload)?;
-    
+
+    // Process the payment webhook via the configured payment connector
+    let payment_details = service.process_payment_webhook(payload)?;
+
     // Update transaction
     service.process_payment(&transaction.transaction_id, payment_details).await?;
-    
+
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
         "message": "Payment webhook processed successfully"
@@ -62,41 +216,99 @@ pub async fn upi_webhook(
 pub async fn wise_webhook(
     service: web::Data<RemittanceService>,
     repo: web::Data<TransactionRepository>,
-    Json(payload): Json<WiseWebhookPayload>,
+    processed_events: web::Data<ProcessedEventFilter>,
+    webhook: VerifiedWebhook<WiseWebhook>,
 ) -> AppResult<HttpResponse> {
-    // Find transaction by transfer_id
+    let payload: WiseWebhookPayload = serde_json::from_slice(&webhook.body)
+        .map_err(|e| AppError::validation_error(format!("Invalid webhook payload: {}", e)))?;
+
+    // Dedupe this specific event (transfer_id + status) before applying any side effects, so a
+    // redelivery or a repeat entry in a batched payload is skipped independently of others
+    if !processed_events.try_claim(&event_key("wise", &format!("{}:{}", payload.transfer_id, payload.status))).await? {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "Transfer webhook already processed"
+        })));
+    }
+
+    // Find transaction by transfer_id, via the TransferIdIndex
     let transfer_id = payload.transfer_id.clone();
-    
-    // Query transactions by transfer_id is not directly supported,
-    // so we need to get all transactions and filter
-    let transactions = repo.get_by_status(TransactionStatus::Transferred, None).await?;
-    
-    let transaction = transactions.into_iter()
-        .find(|t| {
-            t.transfer_details.transfer_id.as_ref().map_or(false, |id| id == &transfer_id)
-        })
+
+    let transaction = repo.get_by_transfer_id(&transfer_id).await?
         .ok_or_else(|| AppError::not_found(format!("Transaction not found for transfer_id: {}", transfer_id)))?;
-    
+
     // Process the Wise webhook based on status
     if payload.status.to_lowercase() == "completed" || payload.status.to_lowercase() == "outgoing_payment_sent" {
         // Mark transaction as COMPLETED
         service.complete_transaction(&transaction.transaction_id).await?;
     } else if payload.status.to_lowercase() == "failed" || payload.status.to_lowercase() == "cancelled" {
-        // Mark transaction as FAILED
-        repo.mark_as_failed(&transaction.transaction_id, &format!("Transfer failed: {}", payload.status)).await?;
+        // Mark transaction as FAILED and refund the debited balance
+        service.fail_transaction(
+            &transaction,
+            &format!("Transfer failed: {}", payload.status),
+            TransactionStatus::Transferred,
+        ).await?;
     }
-    
+
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
         "message": "Transfer webhook processed successfully"
     })))
 }
 
+/// Process deposit webhook, crediting the depositing user's prepaid balance
+#[api_v2_operation(
+    summary = "Deposit Webhook",
+    description = "Receives deposit confirmations that credit a user's prepaid balance",
+    consumes = "application/json",
+    produces = "application/json",
+    tags(name = "Webhooks"),
+)]
+pub async fn deposit_webhook(
+    service: web::Data<RemittanceService>,
+    processed_events: web::Data<ProcessedEventFilter>,
+    webhook: VerifiedWebhook<DepositWebhook>,
+) -> AppResult<HttpResponse> {
+    let payload: serde_json::Value = serde_json::from_slice(&webhook.body)
+        .map_err(|e| AppError::validation_error(format!("Invalid webhook payload: {}", e)))?;
+
+    let reference_id = payload.get("reference_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| AppError::validation_error("Missing reference_id in webhook payload"))?
+        .to_string();
+    let user_id = payload.get("user_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| AppError::validation_error("Missing user_id in webhook payload"))?
+        .to_string();
+    let currency = payload.get("currency").and_then(|v| v.as_str()).unwrap_or("INR");
+    let amount: rust_decimal::Decimal = payload.get("amount")
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| AppError::validation_error("Missing or invalid amount in webhook payload"))?;
+
+    // Dedupe this specific deposit before crediting, so a redelivery or a repeat entry in a
+    // batched payload doesn't double-credit the balance
+    if !processed_events.try_claim(&event_key("deposit", &reference_id)).await? {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "Deposit webhook already processed"
+        })));
+    }
+
+    service.credit_user_balance(&user_id, currency, amount, Some(&reference_id)).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "Deposit webhook processed successfully"
+    })))
+}
+
 /// Configure webhook routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/webhooks")
             .route("/upi-callback", web::post().to(upi_webhook))
-            .route("/wise-callback", web::post().to(wise_webhook)),
+            .route("/wise-callback", web::post().to(wise_webhook))
+            .route("/deposit-callback", web::post().to(deposit_webhook)),
     );
-} 
\ No newline at end of file
+}
@@ -1,4 +1,5 @@
 use config::{Config, ConfigError, Environment, File};
+use secrecy::Secret;
 use serde::Deserialize;
 use std::env;
 
@@ -30,14 +31,32 @@ pub struct DatabaseConfig {
     pub secret_access_key: String,
     pub transactions_table: String,
     pub exchange_rates_table: String,
+    pub ledger_entries_table: String,
+    pub transaction_events_table: String,
+    pub quotes_table: String,
+    pub processed_events_table: String,
+    pub user_ledger_entries_table: String,
+    pub user_balances_table: String,
+    pub idempotency_table: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct PaymentConfig {
     pub upi_api_endpoint: String,
-    pub upi_api_key: String,
+    /// Wrapped in `Secret` so it can't leak through a `Debug` print of `AppConfig`/`PaymentConfig`
+    pub upi_api_key: Secret<String>,
     pub upi_callback_url: String,
     pub upi_timeout_seconds: u64,
+    /// Wrapped in `Secret` so it can't leak through a `Debug` print of `AppConfig`/`PaymentConfig`
+    pub upi_webhook_secret: Secret<String>,
+    /// Previously-active secret, accepted alongside `upi_webhook_secret` during a rotation window
+    pub upi_webhook_secret_previous: Option<Secret<String>>,
+    /// Name of the `PaymentConnector` to use, e.g. `"upi"`
+    pub connector: String,
+    /// Secret used to verify the deposit webhook that credits a user's prepaid balance
+    pub deposit_webhook_secret: String,
+    /// Previously-active secret, accepted alongside `deposit_webhook_secret` during a rotation window
+    pub deposit_webhook_secret_previous: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -46,6 +65,28 @@ pub struct CurrencyConfig {
     pub ad_bank_api_key: String,
     pub ad_bank_client_id: String,
     pub ad_bank_timeout_seconds: u64,
+    /// WebSocket URL for the live INR/CAD ticker feed consumed by `StreamingRateProvider`
+    pub rate_feed_ws_url: String,
+    /// Minimum fractional move in the rate (e.g. `0.0005` for 5bps) before a tick is persisted
+    pub rate_feed_change_threshold: f64,
+    /// A tick repeating the last persisted rate within this window is treated as a duplicate and dropped
+    pub rate_feed_debounce_ms: u64,
+    pub rate_feed_reconnect_base_ms: u64,
+    pub rate_feed_reconnect_max_ms: u64,
+    /// Ordered `RateSource` names to try, e.g. `["dynamo_cached", "live_market"]`; when every
+    /// configured source errors or returns a stale rate, resolution falls back to a
+    /// statically configured `FixedRateSource` rather than failing the quote.
+    pub rate_sources: Vec<String>,
+    /// Rate served by `FixedRateSource` when every other configured source is unavailable
+    pub fixed_fallback_rate: f64,
+    /// Name of the `CurrencyProvider` to use for conversions, e.g. `"ad_bank"`
+    pub currency_provider: String,
+    /// Max retry attempts for a transient AD Bank HTTP failure, via `integrations::retry`
+    pub max_retries: u32,
+    /// Base delay for `integrations::retry`'s exponential backoff, in milliseconds
+    pub retry_base_ms: u64,
+    /// Cap on `integrations::retry`'s exponential backoff, in milliseconds
+    pub retry_max_ms: u64,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -55,6 +96,26 @@ pub struct TransferConfig {
     pub wise_profile_id: String,
     pub wise_callback_url: String,
     pub wise_timeout_seconds: u64,
+    pub wise_webhook_secret: String,
+    /// Previously-active secret, accepted alongside `wise_webhook_secret` during a rotation window
+    pub wise_webhook_secret_previous: Option<String>,
+    /// `PayoutConnector` names to build, e.g. `["wise", "interac"]`. `RemittanceService` tries
+    /// the connectors whose `PayoutConnector::supports` accepts the recipient first, in this
+    /// order, and only then the remaining connectors, also in this order.
+    pub payout_connectors: Vec<String>,
+    /// Destination currency Wise recipient accounts and transfers are created in, e.g. `"CAD"`
+    pub wise_target_currency: String,
+    /// Destination country code for Wise recipient accounts, e.g. `"CA"`
+    pub wise_target_country: String,
+    /// Max retry attempts for a transient Wise or Interac HTTP failure, via `integrations::retry`
+    pub max_retries: u32,
+    /// Base delay for `integrations::retry`'s exponential backoff, in milliseconds
+    pub retry_base_ms: u64,
+    /// Cap on `integrations::retry`'s exponential backoff, in milliseconds
+    pub retry_max_ms: u64,
+    pub interac_api_endpoint: String,
+    pub interac_api_key: String,
+    pub interac_timeout_seconds: u64,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -71,7 +132,38 @@ pub struct BusinessRulesConfig {
     pub fee_percentage: f64,
     pub min_fee_inr: u64,
     pub exchange_rate_cache_seconds: u64,
+    pub exchange_rate_retention_days: u64,
     pub transaction_expiry_hours: u64,
+    /// Percentage of the raw market rate kept as platform margin when quoting the
+    /// customer-facing effective rate, e.g. `1.0` for a 1% spread.
+    pub spread_percentage: f64,
+    /// Upper bound on `calculate_fee`'s output, as a percentage of `source_amount`, so the
+    /// flat minimum fee can't disproportionately eat a small transfer.
+    pub max_relative_fee_percentage: f64,
+    /// How long a `Quote` stays valid before `process_currency_conversion` falls back to a
+    /// fresh rate.
+    pub quote_validity_seconds: u64,
+    /// How long a claimed webhook dedupe key is kept before it's eligible to expire via TTL
+    pub processed_event_retention_days: u64,
+    pub reconciliation_poll_seconds: u64,
+    pub reconciliation_stale_after_secs: i64,
+    pub reconciliation_max_attempts: i64,
+    pub reconciliation_lease_seconds: i64,
+    /// Expected number of distinct webhook event ids live at once, sized so
+    /// `ProcessedEventFilter`'s bloom filter stays under its target false-positive rate.
+    pub webhook_bloom_filter_expected_items: u64,
+    /// Target false-positive rate for `ProcessedEventFilter`'s bloom filter; lower values cost
+    /// more memory per tracked event id.
+    pub webhook_bloom_filter_false_positive_rate: f64,
+    /// How long a claimed `Idempotency-Key` record is kept before it's eligible to expire via TTL
+    pub idempotency_retention_days: u64,
+    /// How far a webhook's timestamp header may drift from now, in either direction, before
+    /// `VerifiedWebhook` rejects it as a possible replay of a captured request
+    pub webhook_timestamp_tolerance_seconds: u64,
+    /// Target false-positive rate for the settlement-reconciliation bloom filter built over
+    /// outstanding transaction references in `ReconciliationService::reconcile`; sized from the
+    /// actual count of pending/transferred transactions fetched at call time.
+    pub settlement_bloom_filter_false_positive_rate: f64,
 }
 
 impl AppConfig {
@@ -2,6 +2,8 @@ use actix_web::{HttpResponse, ResponseError};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::models::{ApiResponseError, ProviderError, ProviderErrorKind};
+
 /// AppError is the main error type for our application
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -31,7 +33,31 @@ pub enum AppError {
     
     #[error("Transaction in invalid state: current={current}, expected={expected}")]
     InvalidStateError { current: String, expected: String },
-    
+
+    #[error("Conflict: {0}")]
+    ConflictError(String),
+
+    #[error("Unauthorized: {0}")]
+    UnauthorizedError(String),
+
+    #[error("Webhook verification failed: {0}")]
+    WebhookVerificationError(String),
+
+    #[error("Quote expired: {0}")]
+    QuoteExpiredError(String),
+
+    #[error("Insufficient balance: {0}")]
+    InsufficientBalanceError(String),
+
+    #[error("Idempotency-Key conflict: {0}")]
+    IdempotencyConflictError(String),
+
+    #[error("Provider error [{}]: {}", .0.code, .0.message)]
+    ProviderError(ProviderError),
+
+    #[error("Gateway error [{}]: {} (debug_id={})", .0.name, .0.message, .0.debug_id.as_deref().unwrap_or("none"))]
+    GatewayError(ApiResponseError),
+
     #[error("External service error: {0}")]
     ExternalServiceError(String),
     
@@ -46,6 +72,10 @@ pub struct ErrorResponse {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_code: Option<String>,
+    /// The gateway's own correlation id for a `GatewayError`, handed back to the caller so it
+    /// can be quoted on a support ticket
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug_id: Option<String>,
 }
 
 impl ResponseError for AppError {
@@ -60,6 +90,52 @@ impl ResponseError for AppError {
             AppError::InvalidStateError { .. } => {
                 HttpResponse::UnprocessableEntity().json(self.to_error_response("422"))
             }
+            AppError::ConflictError(_) => {
+                HttpResponse::Conflict().json(self.to_error_response("409"))
+            }
+            AppError::UnauthorizedError(_) | AppError::WebhookVerificationError(_) => {
+                HttpResponse::Unauthorized().json(self.to_error_response("401"))
+            }
+            AppError::QuoteExpiredError(_) => {
+                HttpResponse::Gone().json(self.to_error_response("410"))
+            }
+            AppError::InsufficientBalanceError(_) => {
+                HttpResponse::UnprocessableEntity().json(self.to_error_response("422"))
+            }
+            AppError::IdempotencyConflictError(_) => {
+                HttpResponse::Conflict().json(self.to_error_response("409"))
+            }
+            AppError::ProviderError(err) => {
+                // Carry the provider's own error code through instead of an HTTP-status
+                // placeholder, and pick the status by what kind of failure it actually was
+                // rather than flattening everything to a 502.
+                let response = ErrorResponse {
+                    status: "error".to_string(),
+                    message: err.message.clone(),
+                    error_code: Some(err.code.clone()),
+                    debug_id: None,
+                };
+
+                match err.kind {
+                    ProviderErrorKind::InsufficientFunds
+                    | ProviderErrorKind::InvalidRecipient
+                    | ProviderErrorKind::RateExpired => HttpResponse::UnprocessableEntity().json(response),
+                    ProviderErrorKind::ProviderUnavailable => HttpResponse::BadGateway().json(response),
+                    ProviderErrorKind::Unknown if err.retryable => HttpResponse::BadGateway().json(response),
+                    ProviderErrorKind::Unknown => HttpResponse::UnprocessableEntity().json(response),
+                }
+            }
+            AppError::GatewayError(err) => {
+                // Surface the gateway's own error name/debug_id instead of flattening to a 502
+                // placeholder, so a caller can branch on `name` and a support ticket can quote
+                // `debug_id` straight from the response body.
+                HttpResponse::BadGateway().json(ErrorResponse {
+                    status: "error".to_string(),
+                    message: err.message.clone(),
+                    error_code: Some(err.name.clone()),
+                    debug_id: err.debug_id.clone(),
+                })
+            }
             AppError::UserServiceError(_)
             | AppError::PaymentError(_)
             | AppError::CurrencyError(_)
@@ -78,6 +154,7 @@ impl AppError {
             status: "error".to_string(),
             message: self.to_string(),
             error_code: Some(code.to_string()),
+            debug_id: None,
         }
     }
     
@@ -99,7 +176,39 @@ impl AppError {
     pub fn database_error(message: impl Into<String>) -> Self {
         AppError::DatabaseError(message.into())
     }
-    
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::ConflictError(message.into())
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        AppError::UnauthorizedError(message.into())
+    }
+
+    pub fn webhook_verification_error(message: impl Into<String>) -> Self {
+        AppError::WebhookVerificationError(message.into())
+    }
+
+    pub fn quote_expired(quote_id: impl Into<String>) -> Self {
+        AppError::QuoteExpiredError(quote_id.into())
+    }
+
+    pub fn insufficient_balance(message: impl Into<String>) -> Self {
+        AppError::InsufficientBalanceError(message.into())
+    }
+
+    pub fn idempotency_conflict(message: impl Into<String>) -> Self {
+        AppError::IdempotencyConflictError(message.into())
+    }
+
+    pub fn from_provider_error(err: ProviderError) -> Self {
+        AppError::ProviderError(err)
+    }
+
+    pub fn from_gateway_error(err: ApiResponseError) -> Self {
+        AppError::GatewayError(err)
+    }
+
     pub fn internal_error(message: impl Into<String>) -> Self {
         AppError::InternalError(message.into())
     }
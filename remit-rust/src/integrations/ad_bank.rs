@@ -1,3 +1,4 @@
+use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -9,6 +10,9 @@ use crate::config::get_config;
 use crate::errors::{AppError, AppResult};
 use crate::models::{ConversionDetails, ExchangeRate};
 
+use super::connector::CurrencyProvider;
+use super::retry::{send_with_retry, RetryPolicy};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetRateRequest {
     pub source_currency: String,
@@ -52,6 +56,7 @@ pub struct AdBankClient {
     base_url: String,
     api_key: String,
     client_id: String,
+    retry_policy: RetryPolicy,
 }
 
 impl AdBankClient {
@@ -59,17 +64,22 @@ impl AdBankClient {
     pub fn new() -> Self {
         let config = get_config();
         let timeout = Duration::from_secs(config.currency.ad_bank_timeout_seconds);
-        
+
         let http_client = Client::builder()
             .timeout(timeout)
             .build()
             .unwrap_or_default();
-            
+
         AdBankClient {
             http_client,
             base_url: config.currency.ad_bank_api_endpoint.clone(),
             api_key: config.currency.ad_bank_api_key.clone(),
             client_id: config.currency.ad_bank_client_id.clone(),
+            retry_policy: RetryPolicy::new(
+                config.currency.max_retries,
+                config.currency.retry_base_ms,
+                config.currency.retry_max_ms,
+            ),
         }
     }
     
@@ -82,11 +92,12 @@ impl AdBankClient {
         };
         
         let url = format!("{}/rates", self.base_url);
-        
-        let response = self.http_client.post(&url)
-            .header("x-api-key", &self.api_key)
-            .json(&request)
-            .send()
+
+        let response = send_with_retry(&self.retry_policy, || {
+            self.http_client.post(&url)
+                .header("x-api-key", &self.api_key)
+                .json(&request)
+        })
             .await
             .map_err(|e| AppError::CurrencyError(format!("Failed to get exchange rate: {}", e)))?;
             
@@ -128,11 +139,16 @@ impl AdBankClient {
         };
         
         let url = format!("{}/convert", self.base_url);
-        
-        let response = self.http_client.post(&url)
-            .header("x-api-key", &self.api_key)
-            .json(&request)
-            .send()
+
+        // `reference_id` is already a fresh UUID generated above and sent as part of the body on
+        // every attempt, so reusing it as the Idempotency-Key lets AD Bank collapse retries of
+        // this non-idempotent POST into the original conversion instead of double-converting.
+        let response = send_with_retry(&self.retry_policy, || {
+            self.http_client.post(&url)
+                .header("x-api-key", &self.api_key)
+                .header("Idempotency-Key", &reference_id)
+                .json(&request)
+        })
             .await
             .map_err(|e| AppError::CurrencyError(format!("Failed to convert currency: {}", e)))?;
             
@@ -153,6 +169,7 @@ impl AdBankClient {
                     conversion_id: Some(conversion_response.conversion_id),
                     conversion_time: Some(conversion_response.timestamp),
                     actual_exchange_rate: Some(rate),
+                    market_exchange_rate: Some(rate),
                     reference_id: Some(reference_id),
                 };
                 
@@ -164,4 +181,19 @@ impl AdBankClient {
             }
         }
     }
-} 
\ No newline at end of file
+}
+
+#[async_trait]
+impl CurrencyProvider for AdBankClient {
+    fn name(&self) -> &'static str {
+        "ad_bank"
+    }
+
+    async fn get_exchange_rate(&self, source_currency: &str, destination_currency: &str) -> AppResult<ExchangeRate> {
+        self.get_exchange_rate(source_currency, destination_currency).await
+    }
+
+    async fn convert_currency(&self, source_currency: &str, destination_currency: &str, source_amount: Decimal) -> AppResult<(ConversionDetails, Decimal, Decimal)> {
+        self.convert_currency(source_currency, destination_currency, source_amount).await
+    }
+}
\ No newline at end of file
@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::errors::AppResult;
+use crate::models::{BankAccountDetails, ConversionDetails, ExchangeRate, PaymentDetails, ProviderSession, TransferDetails};
+
+use super::{AdBankClient, InteracClient, MockPayoutConnector, MockPaymentConnector, PaymentStatus, TransferStatus, UpiClient, WiseClient};
+
+/// A payment collection rail (e.g. UPI), pluggable behind `RemittanceService` so the crate can
+/// add alternate corridors and route per-currency-pair instead of hardcoding one provider.
+#[async_trait]
+pub trait PaymentConnector: Send + Sync {
+    /// Stable name persisted on the transaction so webhooks and the reconciliation worker
+    /// dispatch back to the implementation that created the payment, e.g. `"upi"`.
+    fn name(&self) -> &'static str;
+
+    async fn create_payment(&self, amount: String, description: String) -> AppResult<PaymentDetails>;
+
+    async fn check_status(&self, payment_id: &str) -> AppResult<PaymentStatus>;
+
+    /// Parse and validate a provider-specific webhook body, already deserialized to JSON
+    fn process_webhook(&self, payload: Value) -> AppResult<PaymentDetails>;
+
+    /// Collapse this connector's vendor-specific `PaymentDetails`/`PaymentStatus` into the
+    /// neutral `ProviderSession` shape, so callers that just want "id, status, reference" don't
+    /// need to know which rail produced them. The default implementation is enough for any
+    /// connector that hasn't extended `PaymentDetails` with its own bespoke fields.
+    fn describe_session(&self, details: &PaymentDetails, status: &PaymentStatus) -> ProviderSession {
+        ProviderSession::new(
+            details.payment_id.clone().unwrap_or_default(),
+            format!("{:?}", status),
+            details.reference_id.clone(),
+        )
+    }
+}
+
+/// A payout rail (e.g. Wise), pluggable behind `RemittanceService` with a configurable ordered
+/// fallback list so a failed `transfer_funds` on the primary connector retries on the next.
+#[async_trait]
+pub trait PayoutConnector: Send + Sync {
+    /// Stable name persisted on the transaction so `check_transfer_status`/webhooks dispatch
+    /// back to the implementation that created the transfer, e.g. `"wise"`.
+    fn name(&self) -> &'static str;
+
+    /// Whether this connector can service a payout to `bank_details`, e.g. `InteracClient` only
+    /// handles recipients with an `email` on file. `RemittanceService` tries connectors that
+    /// return `true` here before the ones that return `false`; a connector that matches anything
+    /// (the general wire/SWIFT rail) returns `true` unconditionally.
+    fn supports(&self, bank_details: &BankAccountDetails) -> bool;
+
+    async fn transfer_funds(
+        &self,
+        source_currency: &str,
+        source_amount: &str,
+        bank_details: &BankAccountDetails,
+        description: &str,
+    ) -> AppResult<TransferDetails>;
+
+    async fn check_status(&self, transfer_id: &str) -> AppResult<TransferStatus>;
+
+    /// Collapse this connector's vendor-specific `TransferDetails`/`TransferStatus` into the
+    /// neutral `ProviderSession` shape; see `PaymentConnector::describe_session`.
+    fn describe_session(&self, details: &TransferDetails, status: &TransferStatus) -> ProviderSession {
+        ProviderSession::new(
+            details.transfer_id.clone().unwrap_or_default(),
+            format!("{:?}", status),
+            details.reference_id.clone(),
+        )
+    }
+}
+
+/// An FX rail (e.g. AD Bank), pluggable behind `RemittanceService` so pricing and conversion
+/// aren't hard-wired to one provider's request/response shapes.
+#[async_trait]
+pub trait CurrencyProvider: Send + Sync {
+    /// Stable name recorded on the resulting `ExchangeRate.provider`, e.g. `"ad_bank"`.
+    fn name(&self) -> &'static str;
+
+    async fn get_exchange_rate(&self, source_currency: &str, destination_currency: &str) -> AppResult<ExchangeRate>;
+
+    async fn convert_currency(&self, source_currency: &str, destination_currency: &str, source_amount: Decimal) -> AppResult<(ConversionDetails, Decimal, Decimal)>;
+}
+
+/// Build the configured payment connector from `payment.connector`. Selecting a gateway behind
+/// this one factory, rather than branching on provider at each call site, is what lets the crate
+/// add a PayPal- or PayU-style `PaymentConnector` later without touching `RemittanceService`.
+/// `"mock"` is available for local development and tests; any other unrecognized name falls back
+/// to UPI rather than failing startup.
+pub fn build_payment_connector(name: &str) -> Box<dyn PaymentConnector> {
+    match name {
+        "mock" => Box::new(MockPaymentConnector::new()),
+        _ => Box::new(UpiClient::new()),
+    }
+}
+
+/// Build the configured ordered list of payout connectors. Defaults to `["wise"]` when unset.
+/// Names are looked up against a small capability table (`"wise"`, `"interac"`, `"mock"`);
+/// unrecognized names fall back to Wise. At payout time `RemittanceService` tries the connectors
+/// whose `supports` accepts the recipient first, in this order, then the rest, in this order.
+pub fn build_payout_connectors(names: &[String]) -> Vec<Box<dyn PayoutConnector>> {
+    if names.is_empty() {
+        return vec![Box::new(WiseClient::new())];
+    }
+
+    names.iter().map(|name| -> Box<dyn PayoutConnector> {
+        match name.as_str() {
+            "interac" => Box::new(InteracClient::new()),
+            "mock" => Box::new(MockPayoutConnector::new()),
+            _ => Box::new(WiseClient::new()),
+        }
+    }).collect()
+}
+
+/// Build the configured currency provider. AD Bank is the only one this crate ships today;
+/// unknown names fall back to it rather than failing startup.
+pub fn build_currency_provider(_name: &str) -> Box<dyn CurrencyProvider> {
+    Box::new(AdBankClient::new())
+}
@@ -0,0 +1,179 @@
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::config::get_config;
+use crate::errors::{AppError, AppResult};
+use crate::models::{BankAccountDetails, TransferDetails};
+
+use super::connector::PayoutConnector;
+use super::retry::{send_with_retry, RetryPolicy};
+use super::TransferStatus;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendETransferRequest {
+    pub recipient_email: String,
+    pub recipient_name: String,
+    pub source_amount: String,
+    pub description: String,
+    pub reference_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendETransferResponse {
+    pub transfer_id: String,
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ETransferStatusResponse {
+    pub transfer_id: String,
+    pub status: String,
+}
+
+/// Client for a Canadian Interac e-transfer rail, keyed on the recipient's email rather than a
+/// bank account/SWIFT code. Only handles recipients that have an `email` on file; `WiseClient`
+/// is the catch-all for everyone else.
+pub struct InteracClient {
+    http_client: Client,
+    base_url: String,
+    api_key: String,
+    retry_policy: RetryPolicy,
+}
+
+impl InteracClient {
+    /// Create a new Interac client
+    pub fn new() -> Self {
+        let config = get_config();
+        let timeout = Duration::from_secs(config.transfer.interac_timeout_seconds);
+
+        let http_client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default();
+
+        InteracClient {
+            http_client,
+            base_url: config.transfer.interac_api_endpoint.clone(),
+            api_key: config.transfer.interac_api_key.clone(),
+            retry_policy: RetryPolicy::new(
+                config.transfer.max_retries,
+                config.transfer.retry_base_ms,
+                config.transfer.retry_max_ms,
+            ),
+        }
+    }
+
+    /// Send an e-transfer
+    pub async fn send_e_transfer(
+        &self,
+        source_amount: &str,
+        bank_details: &BankAccountDetails,
+        description: &str,
+    ) -> AppResult<TransferDetails> {
+        let email = bank_details.email.clone()
+            .ok_or_else(|| AppError::validation_error("Recipient has no email on file for Interac".to_string()))?;
+        let reference_id = Uuid::new_v4().to_string();
+
+        let request = SendETransferRequest {
+            recipient_email: email,
+            recipient_name: bank_details.account_holder_name.clone(),
+            source_amount: source_amount.to_string(),
+            description: description.to_string(),
+            reference_id: reference_id.clone(),
+        };
+
+        let url = format!("{}/e-transfers", self.base_url);
+
+        // `reference_id` is a fresh UUID sent in the body on every attempt, so reusing it as the
+        // Idempotency-Key lets a retried send collapse into the original e-transfer.
+        let response = send_with_retry(&self.retry_policy, || {
+            self.http_client.post(&url)
+                .header("x-api-key", &self.api_key)
+                .header("Idempotency-Key", &reference_id)
+                .json(&request)
+        })
+            .await
+            .map_err(|e| AppError::TransferError(format!("Failed to send e-transfer: {}", e)))?;
+
+        match response.status() {
+            StatusCode::OK | StatusCode::CREATED => {
+                let transfer_response = response.json::<SendETransferResponse>()
+                    .await
+                    .map_err(|e| AppError::TransferError(format!("Failed to parse e-transfer response: {}", e)))?;
+
+                Ok(TransferDetails {
+                    transfer_id: Some(transfer_response.transfer_id),
+                    transfer_time: Some(transfer_response.timestamp),
+                    tracking_url: None,
+                    estimated_delivery: None,
+                    reference_id: Some(reference_id),
+                    locked_rate: None,
+                    fee: None,
+                    quote_expires_at: None,
+                })
+            },
+            _ => {
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                Err(AppError::TransferError(format!("Interac returned error: {}", error_text)))
+            }
+        }
+    }
+
+    /// Check the status of an e-transfer
+    pub async fn check_e_transfer_status(&self, transfer_id: &str) -> AppResult<TransferStatus> {
+        let url = format!("{}/e-transfers/{}", self.base_url, transfer_id);
+
+        let response = self.http_client.get(&url)
+            .header("x-api-key", &self.api_key)
+            .send()
+            .await
+            .map_err(|e| AppError::TransferError(format!("Failed to check e-transfer status: {}", e)))?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let status_response = response.json::<ETransferStatusResponse>()
+                    .await
+                    .map_err(|e| AppError::TransferError(format!("Failed to parse e-transfer status: {}", e)))?;
+
+                Ok(TransferStatus::from(status_response.status.as_str()))
+            },
+            StatusCode::NOT_FOUND => {
+                Err(AppError::not_found(format!("E-transfer not found: {}", transfer_id)))
+            },
+            _ => {
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                Err(AppError::TransferError(format!("Interac returned error: {}", error_text)))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PayoutConnector for InteracClient {
+    fn name(&self) -> &'static str {
+        "interac"
+    }
+
+    fn supports(&self, bank_details: &BankAccountDetails) -> bool {
+        bank_details.email.is_some()
+    }
+
+    async fn transfer_funds(
+        &self,
+        _source_currency: &str,
+        source_amount: &str,
+        bank_details: &BankAccountDetails,
+        description: &str,
+    ) -> AppResult<TransferDetails> {
+        self.send_e_transfer(source_amount, bank_details, description).await
+    }
+
+    async fn check_status(&self, transfer_id: &str) -> AppResult<TransferStatus> {
+        self.check_e_transfer_status(transfer_id).await
+    }
+}
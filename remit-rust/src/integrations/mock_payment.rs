@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::PaymentDetails;
+
+use super::connector::PaymentConnector;
+use super::PaymentStatus;
+
+/// A `PaymentConnector` that never calls out to a real gateway, completing every payment
+/// immediately in memory. Selected via `payment.connector = "mock"` for local development and
+/// integration testing environments where no real payment rail is configured.
+pub struct MockPaymentConnector;
+
+impl MockPaymentConnector {
+    pub fn new() -> Self {
+        MockPaymentConnector
+    }
+}
+
+#[async_trait]
+impl PaymentConnector for MockPaymentConnector {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn create_payment(&self, _amount: String, _description: String) -> AppResult<PaymentDetails> {
+        Ok(PaymentDetails {
+            payment_id: Some(format!("mock-{}", Uuid::new_v4())),
+            payment_link: None,
+            payment_time: Some(Utc::now()),
+            reference_id: Some(Uuid::new_v4().to_string()),
+        })
+    }
+
+    async fn check_status(&self, _payment_id: &str) -> AppResult<PaymentStatus> {
+        Ok(PaymentStatus::Completed)
+    }
+
+    fn process_webhook(&self, _payload: Value) -> AppResult<PaymentDetails> {
+        Err(AppError::webhook_verification_error("MockPaymentConnector does not receive webhooks".to_string()))
+    }
+}
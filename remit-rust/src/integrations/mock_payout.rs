@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::errors::AppResult;
+use crate::models::{BankAccountDetails, TransferDetails};
+
+use super::connector::PayoutConnector;
+use super::TransferStatus;
+
+/// A `PayoutConnector` that never calls out to a real provider, completing every transfer
+/// immediately in memory. Selected via `transfer.payout_connectors: ["mock"]` for local
+/// development and integration testing environments where no real payout rail is configured.
+pub struct MockPayoutConnector;
+
+impl MockPayoutConnector {
+    pub fn new() -> Self {
+        MockPayoutConnector
+    }
+}
+
+#[async_trait]
+impl PayoutConnector for MockPayoutConnector {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    /// Accepts any recipient, so it gets picked when it is the first capable connector in the
+    /// configured order, when every capable connector ahead of it fails, or as the only connector
+    /// in a dev/test environment.
+    fn supports(&self, _bank_details: &BankAccountDetails) -> bool {
+        true
+    }
+
+    async fn transfer_funds(
+        &self,
+        _source_currency: &str,
+        _source_amount: &str,
+        _bank_details: &BankAccountDetails,
+        _description: &str,
+    ) -> AppResult<TransferDetails> {
+        Ok(TransferDetails {
+            transfer_id: Some(format!("mock-{}", Uuid::new_v4())),
+            transfer_time: Some(Utc::now()),
+            tracking_url: None,
+            estimated_delivery: None,
+            reference_id: Some(Uuid::new_v4().to_string()),
+            locked_rate: None,
+            fee: None,
+            quote_expires_at: None,
+        })
+    }
+
+    async fn check_status(&self, _transfer_id: &str) -> AppResult<TransferStatus> {
+        Ok(TransferStatus::Completed)
+    }
+}
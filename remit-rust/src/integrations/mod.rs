@@ -2,8 +2,22 @@ pub mod user_service;
 pub mod upi;
 pub mod ad_bank;
 pub mod wise;
+pub mod interac;
+pub mod mock_payout;
+pub mod mock_payment;
+pub mod connector;
+pub mod streaming_rate;
+pub mod rate_source;
+pub mod retry;
 
 pub use user_service::UserServiceClient;
 pub use upi::{UpiClient, PaymentStatus, UpiWebhookPayload};
 pub use ad_bank::AdBankClient;
-pub use wise::{WiseClient, TransferStatus, WiseWebhookPayload}; 
\ No newline at end of file
+pub use wise::{WiseClient, TransferStatus, WiseWebhookPayload};
+pub use interac::InteracClient;
+pub use mock_payout::MockPayoutConnector;
+pub use mock_payment::MockPaymentConnector;
+pub use connector::{PaymentConnector, PayoutConnector, CurrencyProvider, build_payment_connector, build_payout_connectors, build_currency_provider};
+pub use streaming_rate::{LatestRate, StreamingRateProvider};
+pub use rate_source::{RateSource, RateSourceResolver, build_rate_source_resolver};
+pub use retry::{RetryPolicy, send_with_retry}; 
\ No newline at end of file
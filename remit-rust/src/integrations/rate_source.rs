@@ -0,0 +1,152 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+
+use crate::config::get_config;
+use crate::errors::{AppError, AppResult};
+use crate::models::ExchangeRate;
+use crate::repositories::ExchangeRateRepository;
+
+use super::AdBankClient;
+
+/// A way to resolve the current exchange rate for a currency pair. Pluggable so the pricing
+/// path isn't hard-wired to DynamoDB query results: a `RateSourceResolver` tries configured
+/// sources in order and falls back to a `FixedRateSource` rather than surfacing `None`.
+#[async_trait]
+pub trait RateSource: Send + Sync {
+    /// Stable name recorded on the resulting `ExchangeRate.provider` so callers can see which
+    /// source actually served the quote
+    fn name(&self) -> &'static str;
+
+    async fn get_rate(&self, source_currency: &str, destination_currency: &str) -> AppResult<ExchangeRate>;
+}
+
+/// Reads the freshest rate out of `ExchangeRateRepository`, the same lookup
+/// `get_latest` already performs (and the same cache-window staleness rule).
+pub struct DynamoCachedSource {
+    repo: ExchangeRateRepository,
+}
+
+impl DynamoCachedSource {
+    pub fn new(repo: ExchangeRateRepository) -> Self {
+        DynamoCachedSource { repo }
+    }
+}
+
+#[async_trait]
+impl RateSource for DynamoCachedSource {
+    fn name(&self) -> &'static str {
+        "dynamo_cached"
+    }
+
+    async fn get_rate(&self, source_currency: &str, destination_currency: &str) -> AppResult<ExchangeRate> {
+        self.repo.get_latest(source_currency, destination_currency).await?
+            .ok_or_else(|| AppError::CurrencyError("No cached rate within the cache window".to_string()))
+    }
+}
+
+/// Fetches a fresh rate directly from AD Bank, bypassing the cache, for when the cached value
+/// is missing or stale.
+pub struct LiveMarketSource {
+    ad_bank_client: AdBankClient,
+}
+
+impl LiveMarketSource {
+    pub fn new(ad_bank_client: AdBankClient) -> Self {
+        LiveMarketSource { ad_bank_client }
+    }
+}
+
+#[async_trait]
+impl RateSource for LiveMarketSource {
+    fn name(&self) -> &'static str {
+        "live_market"
+    }
+
+    async fn get_rate(&self, source_currency: &str, destination_currency: &str) -> AppResult<ExchangeRate> {
+        self.ad_bank_client.get_exchange_rate(source_currency, destination_currency).await
+    }
+}
+
+/// Always returns a statically configured rate. Useful for tests, sandbox environments, and as
+/// the last-resort fallback when every real source is unavailable.
+pub struct FixedRateSource {
+    rate: Decimal,
+}
+
+impl FixedRateSource {
+    pub fn new(rate: Decimal) -> Self {
+        FixedRateSource { rate }
+    }
+}
+
+#[async_trait]
+impl RateSource for FixedRateSource {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    async fn get_rate(&self, source_currency: &str, destination_currency: &str) -> AppResult<ExchangeRate> {
+        Ok(ExchangeRate::new(
+            source_currency.to_string(),
+            destination_currency.to_string(),
+            self.rate,
+            self.name().to_string(),
+        ))
+    }
+}
+
+/// Tries each configured `RateSource` in order, falling back to a `FixedRateSource` rather than
+/// propagating an error or `None` when every primary source errors or has no fresh rate.
+pub struct RateSourceResolver {
+    sources: Vec<Box<dyn RateSource>>,
+    fallback: FixedRateSource,
+}
+
+impl RateSourceResolver {
+    pub fn new(sources: Vec<Box<dyn RateSource>>, fallback: FixedRateSource) -> Self {
+        RateSourceResolver { sources, fallback }
+    }
+
+    /// Resolve the current rate for `source_currency`/`destination_currency`, trying each
+    /// configured source in order before falling back to the fixed rate
+    pub async fn get_rate(&self, source_currency: &str, destination_currency: &str) -> AppResult<ExchangeRate> {
+        for source in &self.sources {
+            match source.get_rate(source_currency, destination_currency).await {
+                // Tag the result with the source that actually served it, regardless of what
+                // provider name it was originally persisted under
+                Ok(mut rate) => {
+                    rate.provider = source.name().to_string();
+                    return Ok(rate);
+                }
+                Err(e) => tracing::warn!("Rate source '{}' failed, trying next: {}", source.name(), e),
+            }
+        }
+
+        self.fallback.get_rate(source_currency, destination_currency).await
+    }
+}
+
+/// Build the configured ordered `RateSourceResolver` from `CurrencyConfig`. Defaults to
+/// `["dynamo_cached", "live_market"]` when unset; unknown names are skipped.
+pub fn build_rate_source_resolver(exchange_rate_repo: ExchangeRateRepository, ad_bank_client: AdBankClient) -> RateSourceResolver {
+    let config = get_config();
+    let names: Vec<String> = if config.currency.rate_sources.is_empty() {
+        vec!["dynamo_cached".to_string(), "live_market".to_string()]
+    } else {
+        config.currency.rate_sources.clone()
+    };
+
+    let sources: Vec<Box<dyn RateSource>> = names.into_iter().filter_map(|name| -> Option<Box<dyn RateSource>> {
+        match name.as_str() {
+            "dynamo_cached" => Some(Box::new(DynamoCachedSource::new(exchange_rate_repo.clone()))),
+            "live_market" => Some(Box::new(LiveMarketSource::new(ad_bank_client.clone()))),
+            _ => {
+                tracing::warn!("Unknown rate source '{}', skipping", name);
+                None
+            }
+        }
+    }).collect();
+
+    let fallback_rate = Decimal::from_f64(config.currency.fixed_fallback_rate).unwrap_or_default();
+    RateSourceResolver::new(sources, FixedRateSource::new(fallback_rate))
+}
@@ -0,0 +1,65 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Exponential backoff with full jitter for outbound HTTP calls, shared by every integration
+/// client so each one doesn't reimplement its own retry loop.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_ms: u64,
+    pub max_ms: u64,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, base_ms: u64, max_ms: u64) -> Self {
+        RetryPolicy { max_retries, base_ms, max_ms }
+    }
+
+    /// `base * 2^attempt`, capped at `max_ms`, then jittered down to a random value in
+    /// `[0, delay)` so retries from concurrent callers don't all land at once.
+    fn jittered_delay(&self, attempt: u32) -> Duration {
+        let exp_delay = self.base_ms.saturating_mul(1u64 << attempt.min(20)).min(self.max_ms);
+        let jittered = rand::thread_rng().gen_range(0..exp_delay.max(1));
+        Duration::from_millis(jittered)
+    }
+}
+
+/// True for transport-level failures worth retrying: connection failures (including DNS
+/// resolution failures, which reqwest reports as connect errors) and timeouts. Anything else
+/// (body/decode errors) is treated as permanent.
+fn is_retryable_transport_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+/// True for response statuses worth retrying: 502/503/504. Never retries a 4xx (client error,
+/// won't succeed on replay) or any other 5xx that isn't one of the three known-transient codes.
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 502 | 503 | 504)
+}
+
+/// Re-issue `build`'s request until it succeeds, exhausts `policy.max_retries`, or fails with a
+/// non-retryable error/status. `build` is called again on every attempt rather than cloning a
+/// single `RequestBuilder`, so a fresh request (and, for callers that add one, the same
+/// `Idempotency-Key` header) goes out each time.
+pub async fn send_with_retry(
+    policy: &RetryPolicy,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let mut attempt = 0;
+
+    loop {
+        let result = build().send().await;
+
+        let should_retry = match &result {
+            Ok(response) => is_retryable_status(response.status()),
+            Err(e) => is_retryable_transport_error(e),
+        };
+
+        if !should_retry || attempt >= policy.max_retries {
+            return result;
+        }
+
+        tokio::time::sleep(policy.jittered_delay(attempt)).await;
+        attempt += 1;
+    }
+}
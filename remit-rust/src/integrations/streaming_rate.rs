@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::config::get_config;
+use crate::errors::{AppError, AppResult};
+use crate::models::ExchangeRate;
+use crate::repositories::ExchangeRateRepository;
+
+/// An incremental ticker message from the upstream rate feed. Only the fields we need to
+/// build an `ExchangeRate` are modeled; anything else in the message is ignored.
+#[derive(Debug, Deserialize)]
+struct TickerMessage {
+    rate: Decimal,
+}
+
+/// Something that can report the freshest known rate for a currency pair without going back
+/// to a storage layer, e.g. a provider holding a live connection open rather than polling.
+#[async_trait]
+pub trait LatestRate: Send + Sync {
+    /// Return the most recently observed rate, or an error if no tick has arrived yet
+    async fn latest_rate(&mut self) -> AppResult<ExchangeRate>;
+}
+
+/// Holds a WebSocket connection to a live rate feed open and keeps `ExchangeRateRepository`
+/// updated as ticks arrive, so `ExchangeRateRepository::get_latest` always finds a fresh value
+/// instead of relying on `exchange_rate_cache_seconds` as a hard expiry.
+///
+/// `run` is intended to be handed to `tokio::spawn`; it reconnects with backoff on disconnect
+/// and never returns under normal operation. `latest_rate` reads the value `run` last saw via
+/// a `watch` channel, so callers get the live rate without touching DynamoDB.
+pub struct StreamingRateProvider {
+    source_currency: String,
+    destination_currency: String,
+    ws_url: String,
+    change_threshold: Decimal,
+    debounce: Duration,
+    reconnect_base: Duration,
+    reconnect_max: Duration,
+    exchange_rate_repo: ExchangeRateRepository,
+    rate_tx: watch::Sender<Option<ExchangeRate>>,
+    rate_rx: watch::Receiver<Option<ExchangeRate>>,
+}
+
+impl StreamingRateProvider {
+    /// Create a new streaming rate provider for `source_currency`/`destination_currency`
+    pub fn new(source_currency: &str, destination_currency: &str, exchange_rate_repo: ExchangeRateRepository) -> Self {
+        let config = get_config();
+        let (rate_tx, rate_rx) = watch::channel(None);
+
+        StreamingRateProvider {
+            source_currency: source_currency.to_string(),
+            destination_currency: destination_currency.to_string(),
+            ws_url: config.currency.rate_feed_ws_url.clone(),
+            change_threshold: Decimal::from_f64(config.currency.rate_feed_change_threshold).unwrap_or_default(),
+            debounce: Duration::from_millis(config.currency.rate_feed_debounce_ms),
+            reconnect_base: Duration::from_millis(config.currency.rate_feed_reconnect_base_ms),
+            reconnect_max: Duration::from_millis(config.currency.rate_feed_reconnect_max_ms),
+            exchange_rate_repo,
+            rate_tx,
+            rate_rx,
+        }
+    }
+
+    /// Hold the upstream connection open forever, reconnecting with exponential backoff on
+    /// disconnect; intended to be handed to `tokio::spawn`
+    pub async fn run(self) {
+        let mut backoff = self.reconnect_base;
+
+        loop {
+            match self.consume_once().await {
+                Ok(()) => backoff = self.reconnect_base,
+                Err(e) => tracing::warn!("Rate feed connection for {}/{} dropped: {}", self.source_currency, self.destination_currency, e),
+            }
+
+            time::sleep(backoff).await;
+            backoff = (backoff * 2).min(self.reconnect_max);
+        }
+    }
+
+    /// Connect, subscribe to the configured pair, and process ticks until the connection closes
+    async fn consume_once(&self) -> AppResult<()> {
+        let (ws_stream, _) = tokio_tungstenite::connect_async(&self.ws_url)
+            .await
+            .map_err(|e| AppError::CurrencyError(format!("Failed to connect to rate feed: {}", e)))?;
+
+        let (_write, mut read) = ws_stream.split();
+        let mut last_persisted: Option<(Decimal, std::time::Instant)> = None;
+
+        while let Some(message) = read.next().await {
+            let message = message.map_err(|e| AppError::CurrencyError(format!("Rate feed read error: {}", e)))?;
+
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+
+            let tick: TickerMessage = match serde_json::from_str(&text) {
+                Ok(tick) => tick,
+                // Tolerate messages we don't recognize (e.g. a subscription ack) instead of
+                // tearing down the connection over one unparseable frame
+                Err(_) => continue,
+            };
+
+            // Debounce: drop a repeat of the same rate seen again inside the debounce window
+            if let Some((last_rate, last_at)) = last_persisted {
+                if last_rate == tick.rate && last_at.elapsed() < self.debounce {
+                    continue;
+                }
+            }
+
+            let exchange_rate = ExchangeRate::new(
+                self.source_currency.clone(),
+                self.destination_currency.clone(),
+                tick.rate,
+                "streaming".to_string(),
+            );
+
+            let _ = self.rate_tx.send(Some(exchange_rate.clone()));
+
+            // Only persist once the rate has actually moved beyond the configured fractional threshold
+            let moved_enough = match last_persisted {
+                Some((last_rate, _)) if last_rate.is_zero() => true,
+                Some((last_rate, _)) => ((tick.rate - last_rate) / last_rate).abs() >= self.change_threshold,
+                None => true,
+            };
+
+            if moved_enough {
+                self.exchange_rate_repo.save(&exchange_rate).await?;
+                last_persisted = Some((tick.rate, std::time::Instant::now()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A `watch::Receiver` that observes every tick `run` accepts after debouncing, whether or
+    /// not it was persisted, for callers that want to react to updates rather than poll `latest_rate`
+    pub fn subscribe(&self) -> watch::Receiver<Option<ExchangeRate>> {
+        self.rate_rx.clone()
+    }
+}
+
+#[async_trait]
+impl LatestRate for StreamingRateProvider {
+    async fn latest_rate(&mut self) -> AppResult<ExchangeRate> {
+        self.rate_rx.borrow().clone()
+            .ok_or_else(|| AppError::CurrencyError("No rate tick received yet from streaming provider".to_string()))
+    }
+}
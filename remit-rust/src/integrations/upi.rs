@@ -1,12 +1,19 @@
+use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
+use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use sha2::{Digest, Sha512};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::config::get_config;
 use crate::errors::{AppError, AppResult};
-use crate::models::PaymentDetails;
+use crate::models::{ApiResponseError, PaymentDetails};
+
+use super::connector::PaymentConnector;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreatePaymentRequest {
@@ -43,6 +50,33 @@ pub struct UpiWebhookPayload {
     pub reference_id: String,
     pub payment_time: DateTime<Utc>,
     pub upi_transaction_id: String,
+    /// Uppercase hex SHA-512 digest of `payment_id + status + reference_id + payment_time +
+    /// upi_transaction_id + webhook_secret`, in that order, as sent by the gateway
+    pub hash: String,
+}
+
+/// Recompute the Paynow-style integration-key hash over `payload`'s fields (in the fixed order
+/// documented on `UpiWebhookPayload::hash`) and compare it to the received hash in constant time,
+/// so a forged callback without knowledge of `secret` can't mark an unpaid order as completed.
+fn verify_webhook_hash(payload: &UpiWebhookPayload, secret: &str) -> bool {
+    let message = format!(
+        "{}{}{}{}{}{}",
+        payload.payment_id,
+        payload.status,
+        payload.reference_id,
+        payload.payment_time.to_rfc3339(),
+        payload.upi_transaction_id,
+        secret,
+    );
+
+    let expected = hex::encode_upper(Sha512::digest(message.as_bytes()));
+    let actual = payload.hash.as_bytes();
+
+    if expected.len() != actual.len() {
+        return false;
+    }
+
+    expected.as_bytes().iter().zip(actual).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
 }
 
 /// Enum for payment status
@@ -65,12 +99,34 @@ impl From<&str> for PaymentStatus {
     }
 }
 
+/// How long a `create_payment_idempotent` response is kept in `idempotency_cache` before it's
+/// eligible to be purged, analogous to rust-lightning's `IDEMPOTENCY_TIMEOUT_TICKS`: long enough
+/// to cover a client's retry window, short enough that the cache doesn't grow unbounded.
+const IDEMPOTENCY_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Starting delay between `await_completion`'s polls of `check_status`
+const AWAIT_COMPLETION_INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+/// Cap on `await_completion`'s poll delay, reached by doubling the initial backoff
+const AWAIT_COMPLETION_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
 /// Client for UPI Payment Gateway
 pub struct UpiClient {
     http_client: Client,
     base_url: String,
-    api_key: String,
+    /// Wrapped so a stray `Debug`/log of `UpiClient` can't print it; exposed only at the point
+    /// `create_payment_idempotent`/`check_status` set the `x-api-key` header
+    api_key: Secret<String>,
     callback_url: String,
+    /// Wrapped so a stray `Debug`/log of `UpiClient` can't print it; exposed only at the point
+    /// `verify_webhook_hash` recomputes the expected digest
+    webhook_secret: Secret<String>,
+    /// Previously-active webhook secret, checked alongside `webhook_secret` during a rotation
+    /// window so a callback signed before the rotation isn't rejected
+    webhook_secret_previous: Option<Secret<String>>,
+    /// Maps an idempotency key to the `PaymentDetails` already returned for it, so a retried
+    /// `create_payment_idempotent` call within `IDEMPOTENCY_CACHE_TTL` replays the prior result
+    /// instead of creating a second real payment
+    idempotency_cache: Mutex<HashMap<Uuid, (Instant, PaymentDetails)>>,
 }
 
 impl UpiClient {
@@ -78,24 +134,39 @@ impl UpiClient {
     pub fn new() -> Self {
         let config = get_config();
         let timeout = Duration::from_secs(config.payment.upi_timeout_seconds);
-        
+
         let http_client = Client::builder()
             .timeout(timeout)
             .build()
             .unwrap_or_default();
-            
+
         UpiClient {
             http_client,
             base_url: config.payment.upi_api_endpoint.clone(),
             api_key: config.payment.upi_api_key.clone(),
             callback_url: config.payment.upi_callback_url.clone(),
+            webhook_secret: config.payment.upi_webhook_secret.clone(),
+            webhook_secret_previous: config.payment.upi_webhook_secret_previous.clone(),
+            idempotency_cache: Mutex::new(HashMap::new()),
         }
     }
-    
-    /// Create a payment request
-    pub async fn create_payment(&self, amount: String, description: String) -> AppResult<PaymentDetails> {
+
+    /// Create a payment request, returning the `PaymentDetails` previously returned for `key`
+    /// if it was already used within `IDEMPOTENCY_CACHE_TTL`, instead of firing a second POST
+    /// against the gateway. Sends `key` as the `Idempotency-Key` header so the gateway itself
+    /// can also de-duplicate.
+    pub async fn create_payment_idempotent(&self, key: Uuid, amount: String, description: String) -> AppResult<PaymentDetails> {
+        {
+            let mut cache = self.idempotency_cache.lock().unwrap();
+            cache.retain(|_, (recorded_at, _)| recorded_at.elapsed() < IDEMPOTENCY_CACHE_TTL);
+
+            if let Some((_, cached)) = cache.get(&key) {
+                return Ok(cached.clone());
+            }
+        }
+
         let reference_id = Uuid::new_v4().to_string();
-        
+
         let request = CreatePaymentRequest {
             amount,
             currency: "INR".to_string(),
@@ -103,44 +174,52 @@ impl UpiClient {
             reference_id: reference_id.clone(),
             callback_url: self.callback_url.clone(),
         };
-        
+
         let url = format!("{}/payments", self.base_url);
-        
+
         let response = self.http_client.post(&url)
-            .header("x-api-key", &self.api_key)
+            .header("x-api-key", self.api_key.expose_secret())
+            .header("Idempotency-Key", key.to_string())
             .json(&request)
             .send()
             .await
             .map_err(|e| AppError::PaymentError(format!("Failed to create payment: {}", e)))?;
-            
-        match response.status() {
+
+        let payment_details = match response.status() {
             StatusCode::CREATED | StatusCode::OK => {
                 let payment_response = response.json::<CreatePaymentResponse>()
                     .await
                     .map_err(|e| AppError::PaymentError(format!("Failed to parse payment response: {}", e)))?;
-                
-                let payment_details = PaymentDetails {
+
+                PaymentDetails {
                     payment_id: Some(payment_response.payment_id),
                     payment_link: Some(payment_response.payment_link),
                     payment_time: None,
                     reference_id: Some(reference_id),
-                };
-                
-                Ok(payment_details)
+                }
             },
             _ => {
                 let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                Err(AppError::PaymentError(format!("Payment gateway returned error: {}", error_text)))
+                return Err(AppError::from_gateway_error(ApiResponseError::from_response_body(&error_text)));
             }
-        }
+        };
+
+        self.idempotency_cache.lock().unwrap().insert(key, (Instant::now(), payment_details.clone()));
+
+        Ok(payment_details)
+    }
+
+    /// Create a payment request with a freshly generated idempotency key
+    pub async fn create_payment(&self, amount: String, description: String) -> AppResult<PaymentDetails> {
+        self.create_payment_idempotent(Uuid::new_v4(), amount, description).await
     }
-    
+
     /// Check payment status
     pub async fn check_status(&self, payment_id: &str) -> AppResult<PaymentStatus> {
         let url = format!("{}/payments/{}", self.base_url, payment_id);
         
         let response = self.http_client.get(&url)
-            .header("x-api-key", &self.api_key)
+            .header("x-api-key", self.api_key.expose_secret())
             .send()
             .await
             .map_err(|e| AppError::PaymentError(format!("Failed to check payment status: {}", e)))?;
@@ -159,13 +238,48 @@ impl UpiClient {
             },
             _ => {
                 let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                Err(AppError::PaymentError(format!("Payment gateway returned error: {}", error_text)))
+                Err(AppError::from_gateway_error(ApiResponseError::from_response_body(&error_text)))
+            }
+        }
+    }
+
+    /// Poll `check_status` until the payment reaches a terminal status (`Completed`, `Failed`,
+    /// or `Expired`) or `max_wait` elapses, backing off exponentially between calls (starting at
+    /// `AWAIT_COMPLETION_INITIAL_BACKOFF`, doubling up to `AWAIT_COMPLETION_MAX_BACKOFF`) instead
+    /// of hammering the gateway. Mirrors Paynow's poll-URL status-checking workflow, for clients
+    /// that can't expose a callback endpoint for `process_webhook`.
+    pub async fn await_completion(&self, payment_id: &str, max_wait: Duration) -> AppResult<PaymentStatus> {
+        let deadline = Instant::now() + max_wait;
+        let mut backoff = AWAIT_COMPLETION_INITIAL_BACKOFF;
+
+        loop {
+            let status = self.check_status(payment_id).await?;
+
+            if status != PaymentStatus::Pending {
+                return Ok(status);
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(AppError::PaymentError(format!(
+                    "Payment {} did not reach a terminal status within {:?}", payment_id, max_wait
+                )));
             }
+
+            tokio::time::sleep(backoff.min(remaining)).await;
+            backoff = (backoff * 2).min(AWAIT_COMPLETION_MAX_BACKOFF);
         }
     }
-    
+
     /// Process a webhook notification
     pub fn process_webhook(&self, payload: UpiWebhookPayload) -> AppResult<PaymentDetails> {
+        let secrets = std::iter::once(self.webhook_secret.expose_secret().as_str())
+            .chain(self.webhook_secret_previous.as_ref().map(|s| s.expose_secret().as_str()));
+
+        if !secrets.any(|secret| verify_webhook_hash(&payload, secret)) {
+            return Err(AppError::PaymentError("Invalid UPI webhook hash".to_string()));
+        }
+
         // Validate webhook payload
         let status = PaymentStatus::from(payload.status.as_str());
         
@@ -179,7 +293,28 @@ impl UpiClient {
             payment_time: Some(payload.payment_time),
             reference_id: Some(payload.reference_id),
         };
-        
+
         Ok(payment_details)
     }
-} 
\ No newline at end of file
+}
+
+#[async_trait]
+impl PaymentConnector for UpiClient {
+    fn name(&self) -> &'static str {
+        "upi"
+    }
+
+    async fn create_payment(&self, amount: String, description: String) -> AppResult<PaymentDetails> {
+        self.create_payment(amount, description).await
+    }
+
+    async fn check_status(&self, payment_id: &str) -> AppResult<PaymentStatus> {
+        self.check_status(payment_id).await
+    }
+
+    fn process_webhook(&self, payload: serde_json::Value) -> AppResult<PaymentDetails> {
+        let payload: UpiWebhookPayload = serde_json::from_value(payload)
+            .map_err(|e| AppError::validation_error(format!("Invalid UPI webhook payload: {}", e)))?;
+        self.process_webhook(payload)
+    }
+}
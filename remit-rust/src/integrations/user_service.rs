@@ -24,6 +24,10 @@ pub struct RecipientDetails {
     pub bank_name: String,
     pub ifsc_or_swift_code: String,
     pub relationship: String,
+
+    /// Recipient's e-transfer address, present when they're set up to receive Interac payouts
+    /// instead of (or in addition to) a wire/SWIFT transfer.
+    pub email: Option<String>,
 }
 
 /// Client for the User Service API
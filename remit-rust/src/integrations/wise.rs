@@ -1,4 +1,6 @@
-use reqwest::{Client, StatusCode};
+use async_trait::async_trait;
+use reqwest::{Client, Response, StatusCode};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use chrono::{DateTime, Utc};
@@ -6,7 +8,45 @@ use uuid::Uuid;
 
 use crate::config::get_config;
 use crate::errors::{AppError, AppResult};
-use crate::models::{BankAccountDetails, TransferDetails};
+use crate::models::{BankAccountDetails, ProviderError, ProviderErrorKind, TransferDetails};
+
+use super::connector::PayoutConnector;
+use super::retry::{send_with_retry, RetryPolicy};
+
+/// Wise's error response body. Unknown/undocumented codes fall back to `Unknown` classification
+/// rather than failing to parse, since we'd still rather surface a structured error than nothing.
+#[derive(Debug, Clone, Deserialize)]
+struct WiseErrorBody {
+    error_code: Option<String>,
+    error_message: Option<String>,
+}
+
+/// Turn a non-2xx Wise response into a structured `ProviderError` instead of a flattened string,
+/// so callers can distinguish a permanent rejection (bad recipient, expired quote) from a
+/// transient outage worth retrying.
+async fn parse_provider_error(status: StatusCode, response: Response) -> ProviderError {
+    let body_text = response.text().await.unwrap_or_default();
+    let parsed: Option<WiseErrorBody> = serde_json::from_str(&body_text).ok();
+
+    let code = parsed.as_ref()
+        .and_then(|b| b.error_code.clone())
+        .unwrap_or_else(|| status.as_str().to_string());
+    let message = parsed
+        .and_then(|b| b.error_message)
+        .unwrap_or(body_text);
+
+    let kind = match code.as_str() {
+        "insufficient_funds" => ProviderErrorKind::InsufficientFunds,
+        "invalid_recipient" | "recipient_account_invalid" => ProviderErrorKind::InvalidRecipient,
+        "rate_expired" | "quote_expired" => ProviderErrorKind::RateExpired,
+        _ if status.is_server_error() => ProviderErrorKind::ProviderUnavailable,
+        _ => ProviderErrorKind::Unknown,
+    };
+
+    let retryable = matches!(kind, ProviderErrorKind::ProviderUnavailable) || status.as_u16() == 429;
+
+    ProviderError::new(code, message, kind, retryable)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecipientAccountRequest {
@@ -29,6 +69,23 @@ pub struct RecipientAccountResponse {
     pub status: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteRequest {
+    pub profile_id: String,
+    pub source_currency: String,
+    pub target_currency: String,
+    pub source_amount: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteResponse {
+    pub id: String,
+    pub rate: String,
+    pub fee: String,
+    pub target_amount: String,
+    pub rate_expiration_time: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTransferRequest {
     pub source_currency: String,
@@ -97,6 +154,20 @@ impl From<&str> for TransferStatus {
     }
 }
 
+/// Map the crate's neutral `BankAccountDetails` onto Wise's `RecipientAccountRequest` shape,
+/// so the destination currency/country live in config rather than baked into the client.
+fn to_recipient_account_request(profile_id: &str, bank_details: &BankAccountDetails, target_currency: &str, target_country: &str) -> RecipientAccountRequest {
+    RecipientAccountRequest {
+        profile_id: profile_id.to_string(),
+        account_holder_name: bank_details.account_holder_name.clone(),
+        currency: target_currency.to_string(),
+        account_number: bank_details.account_number.clone(),
+        bank_code: bank_details.ifsc_or_swift_code.clone(),
+        bank_name: bank_details.bank_name.clone(),
+        country: target_country.to_string(),
+    }
+}
+
 /// Client for Wise API
 pub struct WiseClient {
     http_client: Client,
@@ -104,6 +175,9 @@ pub struct WiseClient {
     api_key: String,
     profile_id: String,
     callback_url: String,
+    target_currency: String,
+    target_country: String,
+    retry_policy: RetryPolicy,
 }
 
 impl WiseClient {
@@ -111,39 +185,39 @@ impl WiseClient {
     pub fn new() -> Self {
         let config = get_config();
         let timeout = Duration::from_secs(config.transfer.wise_timeout_seconds);
-        
+
         let http_client = Client::builder()
             .timeout(timeout)
             .build()
             .unwrap_or_default();
-            
+
         WiseClient {
             http_client,
             base_url: config.transfer.wise_api_endpoint.clone(),
             api_key: config.transfer.wise_api_key.clone(),
             profile_id: config.transfer.wise_profile_id.clone(),
             callback_url: config.transfer.wise_callback_url.clone(),
+            target_currency: config.transfer.wise_target_currency.clone(),
+            target_country: config.transfer.wise_target_country.clone(),
+            retry_policy: RetryPolicy::new(
+                config.transfer.max_retries,
+                config.transfer.retry_base_ms,
+                config.transfer.retry_max_ms,
+            ),
         }
     }
-    
+
     /// Create a recipient account
     async fn create_recipient_account(&self, bank_details: &BankAccountDetails) -> AppResult<String> {
-        let request = RecipientAccountRequest {
-            profile_id: self.profile_id.clone(),
-            account_holder_name: bank_details.account_holder_name.clone(),
-            currency: "CAD".to_string(),
-            account_number: bank_details.account_number.clone(),
-            bank_code: bank_details.ifsc_or_swift_code.clone(),
-            bank_name: bank_details.bank_name.clone(),
-            country: "CA".to_string(),
-        };
-        
+        let request = to_recipient_account_request(&self.profile_id, bank_details, &self.target_currency, &self.target_country);
+
         let url = format!("{}/accounts", self.base_url);
-        
-        let response = self.http_client.post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&request)
-            .send()
+
+        let response = send_with_retry(&self.retry_policy, || {
+            self.http_client.post(&url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .json(&request)
+        })
             .await
             .map_err(|e| AppError::TransferError(format!("Failed to create recipient account: {}", e)))?;
             
@@ -155,13 +229,47 @@ impl WiseClient {
                 
                 Ok(account_response.id)
             },
-            _ => {
-                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                Err(AppError::TransferError(format!("Wise returned error: {}", error_text)))
+            status => {
+                let provider_error = parse_provider_error(status, response).await;
+                Err(AppError::from_provider_error(provider_error))
             }
         }
     }
     
+    /// Lock in a rate, fee, and target amount for a transfer before it's created, so the
+    /// caller can surface them to the customer and `transfer_funds` can pass the quote's id
+    /// through instead of letting Wise pick a rate at transfer-creation time.
+    async fn create_quote(&self, source_currency: &str, source_amount: &str) -> AppResult<QuoteResponse> {
+        let request = QuoteRequest {
+            profile_id: self.profile_id.clone(),
+            source_currency: source_currency.to_string(),
+            target_currency: self.target_currency.clone(),
+            source_amount: source_amount.to_string(),
+        };
+
+        let url = format!("{}/quotes", self.base_url);
+
+        let response = send_with_retry(&self.retry_policy, || {
+            self.http_client.post(&url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .json(&request)
+        })
+            .await
+            .map_err(|e| AppError::TransferError(format!("Failed to create quote: {}", e)))?;
+
+        match response.status() {
+            StatusCode::OK | StatusCode::CREATED => {
+                response.json::<QuoteResponse>()
+                    .await
+                    .map_err(|e| AppError::TransferError(format!("Failed to parse quote response: {}", e)))
+            },
+            status => {
+                let provider_error = parse_provider_error(status, response).await;
+                Err(AppError::from_provider_error(provider_error))
+            }
+        }
+    }
+
     /// Initiate a transfer to Canada
     pub async fn transfer_funds(
         &self,
@@ -172,49 +280,65 @@ impl WiseClient {
     ) -> AppResult<TransferDetails> {
         // First create a recipient account
         let target_account_id = self.create_recipient_account(bank_details).await?;
-        
+
+        // Lock a rate/fee before committing to the transfer
+        let quote = self.create_quote(source_currency, source_amount).await?;
+        if quote.rate_expiration_time <= Utc::now() {
+            return Err(AppError::TransferError(format!(
+                "Wise quote {} expired before the transfer could be created, please re-quote", quote.id
+            )));
+        }
+
         let reference_id = Uuid::new_v4().to_string();
-        
+
         let request = CreateTransferRequest {
             source_currency: source_currency.to_string(),
             source_amount: source_amount.to_string(),
-            target_currency: "CAD".to_string(),
+            target_currency: self.target_currency.clone(),
             target_account_id,
             profile_id: self.profile_id.clone(),
             reference: description.to_string(),
             payment_purpose: "remittance".to_string(),
-            quote_id: None,
+            quote_id: Some(quote.id.clone()),
             customer_transaction_id: reference_id.clone(),
         };
-        
+
         let url = format!("{}/transfers", self.base_url);
-        
-        let response = self.http_client.post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&request)
-            .send()
+
+        // `reference_id` is already a fresh UUID generated above and sent as part of the body on
+        // every attempt, so reusing it as the Idempotency-Key lets Wise collapse retries of this
+        // non-idempotent POST into the original transfer instead of double-sending money.
+        let response = send_with_retry(&self.retry_policy, || {
+            self.http_client.post(&url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Idempotency-Key", &reference_id)
+                .json(&request)
+        })
             .await
             .map_err(|e| AppError::TransferError(format!("Failed to create transfer: {}", e)))?;
-            
+
         match response.status() {
             StatusCode::OK | StatusCode::CREATED => {
                 let transfer_response = response.json::<CreateTransferResponse>()
                     .await
                     .map_err(|e| AppError::TransferError(format!("Failed to parse transfer response: {}", e)))?;
-                
+
                 let transfer_details = TransferDetails {
                     transfer_id: Some(transfer_response.id),
                     transfer_time: Some(transfer_response.created_at),
                     tracking_url: transfer_response.tracking_url,
                     estimated_delivery: transfer_response.estimated_delivery,
                     reference_id: Some(reference_id),
+                    locked_rate: quote.rate.parse::<Decimal>().ok(),
+                    fee: quote.fee.parse::<Decimal>().ok(),
+                    quote_expires_at: Some(quote.rate_expiration_time),
                 };
-                
+
                 Ok(transfer_details)
             },
-            _ => {
-                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                Err(AppError::TransferError(format!("Wise returned error: {}", error_text)))
+            status => {
+                let provider_error = parse_provider_error(status, response).await;
+                Err(AppError::from_provider_error(provider_error))
             }
         }
     }
@@ -222,10 +346,11 @@ impl WiseClient {
     /// Check transfer status
     pub async fn check_status(&self, transfer_id: &str) -> AppResult<TransferStatus> {
         let url = format!("{}/transfers/{}", self.base_url, transfer_id);
-        
-        let response = self.http_client.get(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .send()
+
+        let response = send_with_retry(&self.retry_policy, || {
+            self.http_client.get(&url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+        })
             .await
             .map_err(|e| AppError::TransferError(format!("Failed to check transfer status: {}", e)))?;
             
@@ -250,30 +375,37 @@ impl WiseClient {
             StatusCode::NOT_FOUND => {
                 Err(AppError::not_found(format!("Transfer not found: {}", transfer_id)))
             },
-            _ => {
-                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                Err(AppError::TransferError(format!("Wise returned error: {}", error_text)))
+            status => {
+                let provider_error = parse_provider_error(status, response).await;
+                Err(AppError::from_provider_error(provider_error))
             }
         }
     }
-    
-    /// Process a webhook notification
-    pub fn process_webhook(&self, payload: WiseWebhookPayload) -> AppResult<TransferDetails> {
-        // Validate webhook payload
-        let status = TransferStatus::from(payload.status.as_str());
-        
-        if status == TransferStatus::Failed {
-            return Err(AppError::TransferError(format!("Transfer failed with status: {}", payload.status)));
-        }
-        
-        let transfer_details = TransferDetails {
-            transfer_id: Some(payload.transfer_id),
-            transfer_time: Some(payload.timestamp),
-            tracking_url: payload.tracking_url,
-            estimated_delivery: payload.estimated_delivery,
-            reference_id: None, // Webhook doesn't provide reference_id
-        };
-        
-        Ok(transfer_details)
+}
+
+#[async_trait]
+impl PayoutConnector for WiseClient {
+    fn name(&self) -> &'static str {
+        "wise"
+    }
+
+    /// Wise is the general wire/SWIFT rail, keyed on `ifsc_or_swift_code`, so it's the catch-all
+    /// connector any recipient can be routed to.
+    fn supports(&self, _bank_details: &BankAccountDetails) -> bool {
+        true
+    }
+
+    async fn transfer_funds(
+        &self,
+        source_currency: &str,
+        source_amount: &str,
+        bank_details: &BankAccountDetails,
+        description: &str,
+    ) -> AppResult<TransferDetails> {
+        self.transfer_funds(source_currency, source_amount, bank_details, description).await
     }
-} 
\ No newline at end of file
+
+    async fn check_status(&self, transfer_id: &str) -> AppResult<TransferStatus> {
+        self.check_status(transfer_id).await
+    }
+}
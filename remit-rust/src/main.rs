@@ -19,8 +19,9 @@ mod repositories;
 mod services;
 
 use config::get_config;
-use repositories::{TransactionRepository, ExchangeRateRepository};
-use services::RemittanceService;
+use integrations::StreamingRateProvider;
+use repositories::{TransactionRepository, ExchangeRateRepository, QuoteRepository, ProcessedEventFilter, ProcessedEventRepository, UserLedgerRepository, IdempotencyRepository};
+use services::{ReconciliationService, ReconciliationWorker, RemittanceService};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -60,11 +61,30 @@ async fn main() -> std::io::Result<()> {
     
     // Create repositories
     let transaction_repo = TransactionRepository::new(dynamodb_client.clone());
-    let exchange_rate_repo = ExchangeRateRepository::new(dynamodb_client);
-    
+    let exchange_rate_repo = ExchangeRateRepository::new(dynamodb_client.clone());
+    let quote_repo = QuoteRepository::new(dynamodb_client.clone());
+    let user_ledger_repo = UserLedgerRepository::new(dynamodb_client.clone());
+    let idempotency_repo = IdempotencyRepository::new(dynamodb_client.clone());
+    let processed_event_repo = ProcessedEventRepository::new(dynamodb_client);
+    let processed_event_filter = ProcessedEventFilter::new(processed_event_repo);
+    if let Err(e) = processed_event_filter.rebuild().await {
+        tracing::warn!("Failed to warm webhook dedupe bloom filter from recent events: {}", e);
+    }
+
+    // Spawn the background worker that keeps the INR/CAD rate fresh from the live feed
+    let rate_feed = StreamingRateProvider::new("INR", "CAD", exchange_rate_repo.clone());
+    tokio::spawn(rate_feed.run());
+
     // Create services
-    let remittance_service = RemittanceService::new(transaction_repo.clone(), exchange_rate_repo);
-    
+    let remittance_service = RemittanceService::new(transaction_repo.clone(), exchange_rate_repo, quote_repo, user_ledger_repo);
+
+    // Spawn the background worker that drives transactions stranded mid-flow forward
+    let reconciliation_worker = ReconciliationWorker::new(transaction_repo.clone(), remittance_service.clone());
+    tokio::spawn(reconciliation_worker.run());
+
+    // On-demand settlement reconciliation, driven by POST /remittance/reconcile rather than a timer
+    let reconciliation_service = ReconciliationService::new(transaction_repo.clone());
+
     // Start HTTP server
     let server_host = config.server.host.clone();
     let server_port = config.server.port;
@@ -97,6 +117,9 @@ async fn main() -> std::io::Result<()> {
                 scope("/api/v1")
                     .app_data(actix_web::web::Data::new(remittance_service.clone()))
                     .app_data(actix_web::web::Data::new(transaction_repo.clone()))
+                    .app_data(actix_web::web::Data::new(idempotency_repo.clone()))
+                    .app_data(actix_web::web::Data::new(processed_event_filter.clone()))
+                    .app_data(actix_web::web::Data::new(reconciliation_service.clone()))
                     .configure(api::configure)
             )
             
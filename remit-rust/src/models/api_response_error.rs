@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// One field-level validation failure within an `ApiResponseError`, e.g. `{"field": "amount",
+/// "issue": "MISSING_REQUIRED_PARAMETER"}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiErrorDetail {
+    pub field: Option<String>,
+    pub issue: String,
+}
+
+/// A HATEOAS link a gateway attaches to an error response, e.g. pointing at a retry endpoint or
+/// documentation for the `name` code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiErrorLink {
+    pub href: String,
+    pub rel: String,
+    pub method: Option<String>,
+}
+
+/// A payment gateway's structured error body, modeled on paypal-rs's `ApiResponseError`, parsed
+/// out of a non-success response instead of flattened into an opaque string. `name` is the
+/// gateway's machine-readable error code (e.g. `"INSTRUMENT_DECLINED"`) that callers can branch
+/// on to distinguish a retriable gateway fault from a permanent validation failure; `debug_id` is
+/// the gateway's own correlation id to hand a support ticket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiResponseError {
+    pub name: String,
+    pub message: String,
+    #[serde(default)]
+    pub debug_id: Option<String>,
+    #[serde(default)]
+    pub details: Vec<ApiErrorDetail>,
+    #[serde(default)]
+    pub links: Vec<ApiErrorLink>,
+}
+
+impl ApiResponseError {
+    /// Parse a non-success gateway response body as an `ApiResponseError`, falling back to a raw
+    /// text error (named `"UNKNOWN_ERROR"`, with the raw body as the message) when the body
+    /// isn't JSON or doesn't match the expected shape.
+    pub fn from_response_body(body: &str) -> Self {
+        serde_json::from_str(body).unwrap_or_else(|_| ApiResponseError {
+            name: "UNKNOWN_ERROR".to_string(),
+            message: body.to_string(),
+            debug_id: None,
+            details: Vec::new(),
+            links: Vec::new(),
+        })
+    }
+}
@@ -1,18 +1,29 @@
 use aws_sdk_dynamodb::model::AttributeValue;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-/// Exchange rate model
+use crate::config::get_config;
+
+/// A single point-in-time exchange rate quote, kept as a time series so a transaction can be
+/// reconciled against the exact rate that was live when it was quoted.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExchangeRate {
     pub date: String,
+
+    /// `{source_currency}_{destination_currency}`, e.g. `"INR_CAD"`.
+    pub currency_pair: String,
+
     pub timestamp: DateTime<Utc>,
     pub source_currency: String,
     pub destination_currency: String,
     pub rate: Decimal,
     pub provider: String,
+
+    /// DynamoDB TTL (epoch seconds); the table's TTL attribute, so stale quotes expire
+    /// automatically instead of growing the time series without bound.
+    pub ttl: i64,
 }
 
 impl ExchangeRate {
@@ -25,31 +36,44 @@ impl ExchangeRate {
     ) -> Self {
         let now = Utc::now();
         let date = now.format("%Y-%m-%d").to_string();
-        
+        let currency_pair = format!("{}_{}", source_currency, destination_currency);
+        let retention_days = get_config().business_rules.exchange_rate_retention_days as i64;
+        let ttl = (now + Duration::days(retention_days)).timestamp();
+
         ExchangeRate {
             date,
+            currency_pair,
             timestamp: now,
             source_currency,
             destination_currency,
             rate,
             provider,
+            ttl,
         }
     }
-    
+
+    /// The customer-facing rate after applying a platform markup (in basis points) on top of
+    /// this rate's mid, e.g. `100` for a 1% markup kept as margin
+    pub fn customer_rate(&self, markup_bps: i32) -> Decimal {
+        self.rate * (Decimal::new(10_000, 4) - Decimal::new(markup_bps as i64, 4)) / Decimal::new(10_000, 4)
+    }
+
     /// Convert to DynamoDB item
     pub fn to_dynamodb_item(&self) -> HashMap<String, AttributeValue> {
         let mut item = HashMap::new();
-        
+
         item.insert("date".to_string(), AttributeValue::S(self.date.clone()));
+        item.insert("currency_pair".to_string(), AttributeValue::S(self.currency_pair.clone()));
         item.insert("timestamp".to_string(), AttributeValue::N(self.timestamp.timestamp().to_string()));
         item.insert("source_currency".to_string(), AttributeValue::S(self.source_currency.clone()));
         item.insert("destination_currency".to_string(), AttributeValue::S(self.destination_currency.clone()));
         item.insert("rate".to_string(), AttributeValue::N(self.rate.to_string()));
         item.insert("provider".to_string(), AttributeValue::S(self.provider.clone()));
-        
+        item.insert("ttl".to_string(), AttributeValue::N(self.ttl.to_string()));
+
         item
     }
-    
+
     /// Convert from DynamoDB item
     pub fn from_dynamodb_item(item: HashMap<String, AttributeValue>) -> Option<Self> {
         let date = item.get("date")?.as_s().ok()?;
@@ -58,21 +82,34 @@ impl ExchangeRate {
         let destination_currency = item.get("destination_currency")?.as_s().ok()?;
         let rate_str = item.get("rate")?.as_n().ok()?;
         let provider = item.get("provider")?.as_s().ok()?;
-        
+
         // Parse timestamp
         let timestamp_ts = timestamp_str.parse::<i64>().ok()?;
         let timestamp = DateTime::from_timestamp(timestamp_ts, 0)?;
-        
+
         // Parse rate
         let rate = rate_str.parse::<Decimal>().ok()?;
-        
+
+        // Rows written before the time-series table existed have no currency_pair/ttl; derive them.
+        let currency_pair = item.get("currency_pair")
+            .and_then(|av| av.as_s().ok())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("{}_{}", source_currency, destination_currency));
+
+        let ttl = item.get("ttl")
+            .and_then(|av| av.as_n().ok())
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
+
         Some(ExchangeRate {
             date: date.to_string(),
+            currency_pair,
             timestamp,
             source_currency: source_currency.to_string(),
             destination_currency: destination_currency.to_string(),
             rate,
             provider: provider.to_string(),
+            ttl,
         })
     }
 } 
\ No newline at end of file
@@ -0,0 +1,111 @@
+use aws_sdk_dynamodb::model::AttributeValue;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::get_config;
+
+/// Whether a claimed `Idempotency-Key` has a response ready to replay yet, so a retry that
+/// arrives while the original attempt is still running can be told to come back later instead
+/// of racing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum IdempotencyStatus {
+    InProgress,
+    Completed,
+}
+
+impl ToString for IdempotencyStatus {
+    fn to_string(&self) -> String {
+        match self {
+            IdempotencyStatus::InProgress => "IN_PROGRESS".to_string(),
+            IdempotencyStatus::Completed => "COMPLETED".to_string(),
+        }
+    }
+}
+
+/// A claimed `Idempotency-Key`, recording the request body hash it was first used with and,
+/// once the original handler finishes, the response a retry with the same key should replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    pub idempotency_key: String,
+    pub request_hash: String,
+    pub status: IdempotencyStatus,
+    pub response_status: Option<u16>,
+    pub response_body: Option<String>,
+    pub created_at: DateTime<Utc>,
+
+    /// DynamoDB TTL (epoch seconds); claimed keys age out instead of growing the table unbounded.
+    pub ttl: i64,
+}
+
+impl IdempotencyRecord {
+    /// Create a new, in-progress idempotency record for `idempotency_key`
+    pub fn new(idempotency_key: String, request_hash: String) -> Self {
+        let now = Utc::now();
+        let retention_days = get_config().business_rules.idempotency_retention_days as i64;
+
+        IdempotencyRecord {
+            idempotency_key,
+            request_hash,
+            status: IdempotencyStatus::InProgress,
+            response_status: None,
+            response_body: None,
+            created_at: now,
+            ttl: (now + Duration::days(retention_days)).timestamp(),
+        }
+    }
+
+    /// Convert to DynamoDB item
+    pub fn to_dynamodb_item(&self) -> HashMap<String, AttributeValue> {
+        let mut item = HashMap::new();
+
+        item.insert("idempotency_key".to_string(), AttributeValue::S(self.idempotency_key.clone()));
+        item.insert("request_hash".to_string(), AttributeValue::S(self.request_hash.clone()));
+        item.insert("status".to_string(), AttributeValue::S(self.status.to_string()));
+        item.insert("created_at".to_string(), AttributeValue::N(self.created_at.timestamp().to_string()));
+        item.insert("ttl".to_string(), AttributeValue::N(self.ttl.to_string()));
+
+        if let Some(response_status) = self.response_status {
+            item.insert("response_status".to_string(), AttributeValue::N(response_status.to_string()));
+        }
+
+        if let Some(ref response_body) = self.response_body {
+            item.insert("response_body".to_string(), AttributeValue::S(response_body.clone()));
+        }
+
+        item
+    }
+
+    /// Convert from DynamoDB item
+    pub fn from_dynamodb_item(item: HashMap<String, AttributeValue>) -> Option<Self> {
+        let idempotency_key = item.get("idempotency_key")?.as_s().ok()?.to_string();
+        let request_hash = item.get("request_hash")?.as_s().ok()?.to_string();
+        let status = match item.get("status")?.as_s().ok()? {
+            "IN_PROGRESS" => IdempotencyStatus::InProgress,
+            "COMPLETED" => IdempotencyStatus::Completed,
+            _ => return None,
+        };
+        let created_at_ts = item.get("created_at")?.as_n().ok()?.parse::<i64>().ok()?;
+        let created_at = DateTime::from_timestamp(created_at_ts, 0)?;
+        let ttl = item.get("ttl")?.as_n().ok()?.parse::<i64>().ok()?;
+
+        let response_status = item.get("response_status")
+            .and_then(|av| av.as_n().ok())
+            .and_then(|s| s.parse::<u16>().ok());
+
+        let response_body = item.get("response_body")
+            .and_then(|av| av.as_s().ok())
+            .map(|s| s.to_string());
+
+        Some(IdempotencyRecord {
+            idempotency_key,
+            request_hash,
+            status,
+            response_status,
+            response_body,
+            created_at,
+            ttl,
+        })
+    }
+}
@@ -0,0 +1,47 @@
+use aws_sdk_dynamodb::model::AttributeValue;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A single double-entry ledger row recording a balance movement on an account,
+/// written atomically alongside the transaction it is attributed to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub entry_id: String,
+    pub transaction_id: String,
+    pub account: String,
+    pub currency: String,
+    /// Positive for a credit to `account`, negative for a debit
+    pub amount: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LedgerEntry {
+    /// Create a new ledger entry
+    pub fn new(transaction_id: String, account: String, currency: String, amount: Decimal) -> Self {
+        LedgerEntry {
+            entry_id: Uuid::new_v4().to_string(),
+            transaction_id,
+            account,
+            currency,
+            amount,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Convert to DynamoDB item
+    pub fn to_dynamodb_item(&self) -> HashMap<String, AttributeValue> {
+        let mut item = HashMap::new();
+
+        item.insert("entry_id".to_string(), AttributeValue::S(self.entry_id.clone()));
+        item.insert("transaction_id".to_string(), AttributeValue::S(self.transaction_id.clone()));
+        item.insert("account".to_string(), AttributeValue::S(self.account.clone()));
+        item.insert("currency".to_string(), AttributeValue::S(self.currency.clone()));
+        item.insert("amount".to_string(), AttributeValue::N(self.amount.to_string()));
+        item.insert("created_at".to_string(), AttributeValue::N(self.created_at.timestamp().to_string()));
+
+        item
+    }
+}
@@ -1,8 +1,29 @@
 pub mod transaction;
 pub mod exchange_rate;
+pub mod page;
+pub mod ledger;
+pub mod transaction_event;
+pub mod quote;
+pub mod processed_event;
+pub mod provider_session;
+pub mod provider_error;
+pub mod api_response_error;
+pub mod user_ledger;
+pub mod idempotency_record;
 
 pub use transaction::{
     Transaction, TransactionStatus, BankAccountDetails,
     PaymentDetails, ConversionDetails, TransferDetails,
+    allowed_prev_statuses,
 };
-pub use exchange_rate::ExchangeRate; 
\ No newline at end of file
+pub use exchange_rate::ExchangeRate;
+pub use page::Page;
+pub use ledger::LedgerEntry;
+pub use transaction_event::TransactionEvent;
+pub use quote::Quote;
+pub use processed_event::ProcessedEvent;
+pub use provider_session::ProviderSession;
+pub use provider_error::{ProviderError, ProviderErrorKind};
+pub use api_response_error::{ApiResponseError, ApiErrorDetail, ApiErrorLink};
+pub use user_ledger::{UserLedgerEntry, UserLedgerEntryKind};
+pub use idempotency_record::{IdempotencyRecord, IdempotencyStatus};
\ No newline at end of file
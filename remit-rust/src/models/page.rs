@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+/// A single page of results with an opaque cursor for fetching the next page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
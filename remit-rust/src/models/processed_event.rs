@@ -0,0 +1,42 @@
+use aws_sdk_dynamodb::model::AttributeValue;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::get_config;
+
+/// Marks a webhook delivery as processed, keyed by its source plus the provider's event id, so a duplicate
+/// delivery (provider retry, at-least-once queue redelivery) doesn't double-apply its side effects.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessedEvent {
+    pub event_key: String,
+    pub processed_at: DateTime<Utc>,
+
+    /// DynamoDB TTL (epoch seconds); claimed keys age out instead of growing the table unbounded.
+    pub ttl: i64,
+}
+
+impl ProcessedEvent {
+    /// Create a new processed-event record for `event_key`
+    pub fn new(event_key: String) -> Self {
+        let now = Utc::now();
+        let retention_days = get_config().business_rules.processed_event_retention_days as i64;
+
+        ProcessedEvent {
+            event_key,
+            processed_at: now,
+            ttl: (now + Duration::days(retention_days)).timestamp(),
+        }
+    }
+
+    /// Convert to DynamoDB item
+    pub fn to_dynamodb_item(&self) -> HashMap<String, AttributeValue> {
+        let mut item = HashMap::new();
+
+        item.insert("event_key".to_string(), AttributeValue::S(self.event_key.clone()));
+        item.insert("processed_at".to_string(), AttributeValue::N(self.processed_at.timestamp().to_string()));
+        item.insert("ttl".to_string(), AttributeValue::N(self.ttl.to_string()));
+
+        item
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// Machine-readable classification of a `ProviderError`, used to pick the HTTP status
+/// `ResponseError` returns and to tell the retry layer whether the call that produced it is
+/// worth retrying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProviderErrorKind {
+    InsufficientFunds,
+    InvalidRecipient,
+    RateExpired,
+    ProviderUnavailable,
+    Unknown,
+}
+
+/// A provider's error response, parsed out of its vendor-specific body instead of flattened into
+/// an opaque string, so callers can tell "retry later" from "bad input" from "permanently failed".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderError {
+    /// The provider's own machine-readable error code, e.g. `"insufficient_funds"`; surfaced to
+    /// API clients via `ErrorResponse.error_code` instead of an HTTP-status placeholder.
+    pub code: String,
+
+    /// The provider's human-readable error message, or the raw response body if it didn't parse
+    pub message: String,
+
+    pub kind: ProviderErrorKind,
+
+    /// Whether this particular failure is expected to succeed on replay
+    pub retryable: bool,
+}
+
+impl ProviderError {
+    pub fn new(code: impl Into<String>, message: impl Into<String>, kind: ProviderErrorKind, retryable: bool) -> Self {
+        ProviderError {
+            code: code.into(),
+            message: message.into(),
+            kind,
+            retryable,
+        }
+    }
+}
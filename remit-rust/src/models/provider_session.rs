@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A provider-agnostic view of a payment or transfer in flight, returned by `PaymentConnector`
+/// and `PayoutConnector` alongside their vendor-specific details so orchestration code can
+/// reason about "some session at some provider" without matching on which rail created it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderSession {
+    /// The vendor's own id for this payment/transfer (`payment_id` / `transfer_id`)
+    pub id: String,
+
+    /// Debug-formatted `PaymentStatus`/`TransferStatus`, e.g. `"Completed"`
+    pub status: String,
+
+    /// The vendor's correlation id, if any, used to look the originating transaction back up
+    pub reference: Option<String>,
+
+    /// Anything else the provider reported, kept as opaque JSON instead of widening this
+    /// struct for every rail's vendor-specific fields
+    pub raw: HashMap<String, Value>,
+}
+
+impl ProviderSession {
+    pub fn new(id: String, status: String, reference: Option<String>) -> Self {
+        ProviderSession {
+            id,
+            status,
+            reference,
+            raw: HashMap::new(),
+        }
+    }
+
+    /// Attach a vendor-specific field that doesn't have a place on the neutral struct
+    pub fn with_raw(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.raw.insert(key.into(), value);
+        self
+    }
+}
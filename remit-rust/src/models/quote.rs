@@ -0,0 +1,117 @@
+use aws_sdk_dynamodb::model::AttributeValue;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::config::get_config;
+
+/// A locked-rate quote: the fee, effective exchange rate, and destination amount computed at
+/// quote time, held fixed until `expires_at` so a transaction bound to it is protected from
+/// rate moves between when the customer sees a price and when funds actually convert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quote {
+    pub quote_id: String,
+    pub source_amount: Decimal,
+    pub source_currency: String,
+    pub destination_currency: String,
+    pub fee: Decimal,
+    pub exchange_rate: Decimal,
+    pub destination_amount: Decimal,
+
+    /// Name of the `RateSource` the locked `exchange_rate` was sourced from, e.g. `"live_market"`,
+    /// so a quote's rate can be traced back to whoever priced it.
+    pub provider: String,
+
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Quote {
+    /// Create a new locked-rate quote, valid for `business_rules.quote_validity_seconds`
+    pub fn new(
+        source_amount: Decimal,
+        source_currency: String,
+        destination_currency: String,
+        fee: Decimal,
+        exchange_rate: Decimal,
+        destination_amount: Decimal,
+        provider: String,
+    ) -> Self {
+        let now = Utc::now();
+        let validity_seconds = get_config().business_rules.quote_validity_seconds as i64;
+
+        Quote {
+            quote_id: Uuid::new_v4().to_string(),
+            source_amount,
+            source_currency,
+            destination_currency,
+            fee,
+            exchange_rate,
+            destination_amount,
+            provider,
+            created_at: now,
+            expires_at: now + Duration::seconds(validity_seconds),
+        }
+    }
+
+    /// Whether this quote can still be honored, i.e. hasn't passed `expires_at`
+    pub fn is_valid(&self) -> bool {
+        Utc::now() < self.expires_at
+    }
+
+    /// Convert to DynamoDB item
+    pub fn to_dynamodb_item(&self) -> HashMap<String, AttributeValue> {
+        let mut item = HashMap::new();
+
+        item.insert("quote_id".to_string(), AttributeValue::S(self.quote_id.clone()));
+        item.insert("source_amount".to_string(), AttributeValue::N(self.source_amount.to_string()));
+        item.insert("source_currency".to_string(), AttributeValue::S(self.source_currency.clone()));
+        item.insert("destination_currency".to_string(), AttributeValue::S(self.destination_currency.clone()));
+        item.insert("fee".to_string(), AttributeValue::N(self.fee.to_string()));
+        item.insert("exchange_rate".to_string(), AttributeValue::N(self.exchange_rate.to_string()));
+        item.insert("destination_amount".to_string(), AttributeValue::N(self.destination_amount.to_string()));
+        item.insert("provider".to_string(), AttributeValue::S(self.provider.clone()));
+        item.insert("created_at".to_string(), AttributeValue::N(self.created_at.timestamp().to_string()));
+        item.insert("expires_at".to_string(), AttributeValue::N(self.expires_at.timestamp().to_string()));
+        // DynamoDB TTL attribute, so expired quotes age out of the table automatically
+        item.insert("ttl".to_string(), AttributeValue::N(self.expires_at.timestamp().to_string()));
+
+        item
+    }
+
+    /// Convert from DynamoDB item
+    pub fn from_dynamodb_item(item: HashMap<String, AttributeValue>) -> Option<Self> {
+        let quote_id = item.get("quote_id")?.as_s().ok()?;
+        let source_amount = item.get("source_amount")?.as_n().ok()?.parse::<Decimal>().ok()?;
+        let source_currency = item.get("source_currency")?.as_s().ok()?;
+        let destination_currency = item.get("destination_currency")?.as_s().ok()?;
+        let fee = item.get("fee")?.as_n().ok()?.parse::<Decimal>().ok()?;
+        let exchange_rate = item.get("exchange_rate")?.as_n().ok()?.parse::<Decimal>().ok()?;
+        let destination_amount = item.get("destination_amount")?.as_n().ok()?.parse::<Decimal>().ok()?;
+        // Rows written before provider tracing was added have no provider attribute.
+        let provider = item.get("provider")
+            .and_then(|av| av.as_s().ok())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let created_at_ts = item.get("created_at")?.as_n().ok()?.parse::<i64>().ok()?;
+        let expires_at_ts = item.get("expires_at")?.as_n().ok()?.parse::<i64>().ok()?;
+        let created_at = DateTime::from_timestamp(created_at_ts, 0)?;
+        let expires_at = DateTime::from_timestamp(expires_at_ts, 0)?;
+
+        Some(Quote {
+            quote_id: quote_id.to_string(),
+            source_amount,
+            source_currency: source_currency.to_string(),
+            destination_currency: destination_currency.to_string(),
+            fee,
+            exchange_rate,
+            destination_amount,
+            provider,
+            created_at,
+            expires_at,
+        })
+    }
+}
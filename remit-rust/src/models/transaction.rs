@@ -6,6 +6,8 @@ use std::collections::HashMap;
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::errors::{AppError, AppResult};
+
 /// Represents the state of a remittance transaction
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "UPPERCASE")]
@@ -37,6 +39,26 @@ impl Default for TransactionStatus {
     }
 }
 
+/// Statuses a transaction may legally advance *from* to reach `target`.
+///
+/// Encodes `Pending -> Funded -> Converted -> Transferred -> Completed`, with
+/// any non-terminal status allowed to move to `Failed`.
+pub fn allowed_prev_statuses(target: &TransactionStatus) -> &'static [TransactionStatus] {
+    match target {
+        TransactionStatus::Pending => &[],
+        TransactionStatus::Funded => &[TransactionStatus::Pending],
+        TransactionStatus::Converted => &[TransactionStatus::Funded],
+        TransactionStatus::Transferred => &[TransactionStatus::Converted],
+        TransactionStatus::Completed => &[TransactionStatus::Transferred],
+        TransactionStatus::Failed => &[
+            TransactionStatus::Pending,
+            TransactionStatus::Funded,
+            TransactionStatus::Converted,
+            TransactionStatus::Transferred,
+        ],
+    }
+}
+
 /// Bank account details for recipient
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct BankAccountDetails {
@@ -51,6 +73,11 @@ pub struct BankAccountDetails {
     
     #[validate(length(min = 1, max = 50))]
     pub ifsc_or_swift_code: String,
+
+    /// Recipient's e-transfer address, present when the recipient is set up for Interac instead
+    /// of a wire/SWIFT payout. `PayoutConnector::supports` uses this to route to `InteracClient`.
+    #[validate(email)]
+    pub email: Option<String>,
 }
 
 /// UPI payment details
@@ -67,7 +94,15 @@ pub struct PaymentDetails {
 pub struct ConversionDetails {
     pub conversion_id: Option<String>,
     pub conversion_time: Option<DateTime<Utc>>,
+
+    /// The customer-facing rate actually applied to this conversion, i.e. the market rate
+    /// with the platform markup already taken out
     pub actual_exchange_rate: Option<Decimal>,
+
+    /// The raw market mid rate at conversion time, before the platform markup; absent on
+    /// transactions converted before this field existed
+    pub market_exchange_rate: Option<Decimal>,
+
     pub reference_id: Option<String>,
 }
 
@@ -79,13 +114,32 @@ pub struct TransferDetails {
     pub tracking_url: Option<String>,
     pub estimated_delivery: Option<DateTime<Utc>>,
     pub reference_id: Option<String>,
+
+    /// Rate locked in by the quote the transfer was created from; absent on transfers created
+    /// before quote locking existed
+    pub locked_rate: Option<Decimal>,
+
+    /// Fee quoted for this transfer
+    pub fee: Option<Decimal>,
+
+    /// When the locked quote (and therefore `locked_rate`/`fee`) stops being honored
+    pub quote_expires_at: Option<DateTime<Utc>>,
 }
 
 /// Main transaction model
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct Transaction {
     pub transaction_id: String,
-    
+
+    /// Optimistic-concurrency version, bumped on every successful repository update.
+    pub version: i64,
+
+    /// Monotonically increasing creation sequence, assigned by `TransactionRepository::next_row_id`
+    /// and indexed by `UserIdRowIdIndex`. Used as the opaque cursor for `/remittance/history`
+    /// instead of `created_at`, which isn't fine-grained enough to order transactions created
+    /// within the same second.
+    pub row_id: i64,
+
     #[validate(length(min = 1, max = 50))]
     pub user_id: String,
     
@@ -121,9 +175,35 @@ pub struct Transaction {
     pub transfer_details: TransferDetails,
     
     pub failure_reason: Option<String>,
-    
+
     #[validate(length(min = 0, max = 500))]
     pub notes: Option<String>,
+
+    /// Client-supplied key used to dedupe retried creation requests, indexed by `IdempotencyKeyIndex`.
+    pub idempotency_key: Option<String>,
+
+    /// Number of times a reconciliation worker has claimed this transaction for retry.
+    pub attempt_count: i64,
+
+    /// While set and in the future, this transaction is leased to a reconciliation worker
+    /// and should not be claimed again.
+    pub lease_until: Option<DateTime<Utc>>,
+
+    /// Denormalized copy of `payment_details.reference_id`, indexed by `PaymentReferenceIdIndex`
+    /// so the UPI webhook can look a transaction up directly instead of scanning by status.
+    pub payment_reference_id: Option<String>,
+
+    /// Denormalized copy of `transfer_details.transfer_id`, indexed by `TransferIdIndex`
+    /// so the Wise webhook can look a transaction up directly instead of scanning by status.
+    pub transfer_id: Option<String>,
+
+    /// Name of the `PayoutConnector` that created `transfer_details`, e.g. `"wise"`, so
+    /// `check_transfer_status` dispatches back to the same connector instead of the configured default.
+    pub connector_name: Option<String>,
+
+    /// ID of the `Quote` this transaction is locked to, if created via one. `process_currency_conversion`
+    /// honors this quote's rate while it's still valid instead of fetching a fresh one.
+    pub quote_id: Option<String>,
 }
 
 impl Transaction {
@@ -135,11 +215,16 @@ impl Transaction {
         recipient_account_details: BankAccountDetails,
         notes: Option<String>,
         fees: Decimal,
+        idempotency_key: Option<String>,
+        quote_id: Option<String>,
+        row_id: i64,
     ) -> Self {
         let now = Utc::now();
-        
+
         Transaction {
             transaction_id: Uuid::new_v4().to_string(),
+            version: 0,
+            row_id,
             user_id,
             status: TransactionStatus::Pending,
             created_at: now,
@@ -157,14 +242,23 @@ impl Transaction {
             transfer_details: TransferDetails::default(),
             failure_reason: None,
             notes,
+            idempotency_key,
+            attempt_count: 0,
+            lease_until: None,
+            payment_reference_id: None,
+            transfer_id: None,
+            connector_name: None,
+            quote_id,
         }
     }
-    
+
     /// Convert Transaction to DynamoDB item
     pub fn to_dynamodb_item(&self) -> HashMap<String, AttributeValue> {
         let mut item = HashMap::new();
         
         item.insert("transaction_id".to_string(), AttributeValue::S(self.transaction_id.clone()));
+        item.insert("version".to_string(), AttributeValue::N(self.version.to_string()));
+        item.insert("row_id".to_string(), AttributeValue::N(self.row_id.to_string()));
         item.insert("user_id".to_string(), AttributeValue::S(self.user_id.clone()));
         item.insert("status".to_string(), AttributeValue::S(self.status.to_string()));
         item.insert("created_at".to_string(), AttributeValue::N(self.created_at.timestamp().to_string()));
@@ -207,13 +301,49 @@ impl Transaction {
         if let Some(ref notes) = self.notes {
             item.insert("notes".to_string(), AttributeValue::S(notes.clone()));
         }
-        
+
+        if let Some(ref idempotency_key) = self.idempotency_key {
+            item.insert("idempotency_key".to_string(), AttributeValue::S(idempotency_key.clone()));
+        }
+
+        item.insert("attempt_count".to_string(), AttributeValue::N(self.attempt_count.to_string()));
+
+        if let Some(ref lease_until) = self.lease_until {
+            item.insert("lease_until".to_string(), AttributeValue::N(lease_until.timestamp().to_string()));
+        }
+
+        if let Some(ref payment_reference_id) = self.payment_reference_id {
+            item.insert("payment_reference_id".to_string(), AttributeValue::S(payment_reference_id.clone()));
+        }
+
+        if let Some(ref transfer_id) = self.transfer_id {
+            item.insert("transfer_id".to_string(), AttributeValue::S(transfer_id.clone()));
+        }
+
+        if let Some(ref connector_name) = self.connector_name {
+            item.insert("connector_name".to_string(), AttributeValue::S(connector_name.clone()));
+        }
+
+        if let Some(ref quote_id) = self.quote_id {
+            item.insert("quote_id".to_string(), AttributeValue::S(quote_id.clone()));
+        }
+
         item
     }
     
     /// Convert DynamoDB item to Transaction
     pub fn from_dynamodb_item(item: HashMap<String, AttributeValue>) -> Option<Self> {
         let transaction_id = item.get("transaction_id")?.as_s().ok()?;
+        // Rows written before optimistic locking was introduced have no version; treat as 0.
+        let version = item.get("version")
+            .and_then(|av| av.as_n().ok())
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
+        // Rows written before row-based history pagination was introduced have no row_id.
+        let row_id = item.get("row_id")
+            .and_then(|av| av.as_n().ok())
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
         let user_id = item.get("user_id")?.as_s().ok()?;
         let status_str = item.get("status")?.as_s().ok()?;
         let created_at_str = item.get("created_at")?.as_n().ok()?;
@@ -263,7 +393,37 @@ impl Transaction {
         let notes = item.get("notes")
             .and_then(|av| av.as_s().ok())
             .map(|s| s.to_string());
-        
+
+        let idempotency_key = item.get("idempotency_key")
+            .and_then(|av| av.as_s().ok())
+            .map(|s| s.to_string());
+
+        let attempt_count = item.get("attempt_count")
+            .and_then(|av| av.as_n().ok())
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
+
+        let lease_until = item.get("lease_until")
+            .and_then(|av| av.as_n().ok())
+            .and_then(|s| s.parse::<i64>().ok())
+            .and_then(|ts| DateTime::from_timestamp(ts, 0));
+
+        let payment_reference_id = item.get("payment_reference_id")
+            .and_then(|av| av.as_s().ok())
+            .map(|s| s.to_string());
+
+        let transfer_id = item.get("transfer_id")
+            .and_then(|av| av.as_s().ok())
+            .map(|s| s.to_string());
+
+        let quote_id = item.get("quote_id")
+            .and_then(|av| av.as_s().ok())
+            .map(|s| s.to_string());
+
+        let connector_name = item.get("connector_name")
+            .and_then(|av| av.as_s().ok())
+            .map(|s| s.to_string());
+
         // Parse complex JSON types
         let recipient_account_details = item.get("recipient_account_details")
             .and_then(|av| av.as_s().ok())
@@ -273,6 +433,7 @@ impl Transaction {
                 account_number: "Unknown".to_string(),
                 account_holder_name: "Unknown".to_string(),
                 ifsc_or_swift_code: "Unknown".to_string(),
+                email: None,
             });
         
         let payment_details = item.get("payment_details")
@@ -292,6 +453,8 @@ impl Transaction {
         
         Some(Transaction {
             transaction_id: transaction_id.to_string(),
+            version,
+            row_id,
             user_id: user_id.to_string(),
             status,
             created_at,
@@ -309,19 +472,49 @@ impl Transaction {
             transfer_details,
             failure_reason,
             notes,
+            idempotency_key,
+            attempt_count,
+            lease_until,
+            payment_reference_id,
+            transfer_id,
+            connector_name,
+            quote_id,
         })
     }
     
-    /// Update transaction status and set updated_at to current time
-    pub fn update_status(&mut self, status: TransactionStatus) {
+    /// Attempt a checked transition to `status`, rejecting it with a descriptive `AppError`
+    /// if `self.status` isn't one of `allowed_prev_statuses(&status)`, or if the sub-details
+    /// that status implies haven't been populated yet (e.g. a `payment_id` before `Funded`).
+    /// `status`/`updated_at` are only touched on success, so a rejected transition leaves the
+    /// transaction untouched rather than landing it in a corrupt state.
+    pub fn transition_to(&mut self, status: TransactionStatus) -> AppResult<()> {
+        let allowed_prev = allowed_prev_statuses(&status);
+        if !allowed_prev.contains(&self.status) {
+            return Err(AppError::invalid_state(self.status.to_string(), status.to_string()));
+        }
+
+        match status {
+            TransactionStatus::Funded if self.payment_details.payment_id.is_none() => {
+                return Err(AppError::validation_error("Cannot advance to FUNDED before a payment_id is recorded"));
+            }
+            TransactionStatus::Converted if self.conversion_details.actual_exchange_rate.is_none() => {
+                return Err(AppError::validation_error("Cannot advance to CONVERTED before conversion_details is recorded"));
+            }
+            TransactionStatus::Transferred if self.transfer_details.transfer_id.is_none() => {
+                return Err(AppError::validation_error("Cannot advance to TRANSFERRED before a transfer_id is recorded"));
+            }
+            _ => {}
+        }
+
         self.status = status;
         self.updated_at = Utc::now();
+        Ok(())
     }
-    
-    /// Mark transaction as failed with a reason
-    pub fn mark_as_failed(&mut self, reason: String) {
-        self.status = TransactionStatus::Failed;
+
+    /// Transition to `Failed` with a reason, valid from any non-terminal status
+    pub fn fail(&mut self, reason: String) -> AppResult<()> {
+        self.transition_to(TransactionStatus::Failed)?;
         self.failure_reason = Some(reason);
-        self.updated_at = Utc::now();
+        Ok(())
     }
 } 
\ No newline at end of file
@@ -0,0 +1,106 @@
+use aws_sdk_dynamodb::model::AttributeValue;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::TransactionStatus;
+
+/// A single append-only audit record of a transaction state change, keyed on
+/// `(transaction_id, event_timestamp)` so the event log can never diverge from the
+/// main record it describes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionEvent {
+    pub transaction_id: String,
+    pub event_timestamp: DateTime<Utc>,
+    pub previous_status: Option<TransactionStatus>,
+    pub new_status: TransactionStatus,
+    pub actor: String,
+    pub reason: Option<String>,
+    /// JSON snapshot of the fields that changed alongside the status
+    pub changed_fields: Option<String>,
+}
+
+impl TransactionEvent {
+    /// Create a new transaction event
+    pub fn new(
+        transaction_id: String,
+        previous_status: Option<TransactionStatus>,
+        new_status: TransactionStatus,
+        actor: String,
+        reason: Option<String>,
+        changed_fields: Option<String>,
+    ) -> Self {
+        TransactionEvent {
+            transaction_id,
+            event_timestamp: Utc::now(),
+            previous_status,
+            new_status,
+            actor,
+            reason,
+            changed_fields,
+        }
+    }
+
+    /// Convert to DynamoDB item
+    pub fn to_dynamodb_item(&self) -> HashMap<String, AttributeValue> {
+        let mut item = HashMap::new();
+
+        item.insert("transaction_id".to_string(), AttributeValue::S(self.transaction_id.clone()));
+        item.insert("event_timestamp".to_string(), AttributeValue::N(self.event_timestamp.timestamp_millis().to_string()));
+        item.insert("new_status".to_string(), AttributeValue::S(self.new_status.to_string()));
+        item.insert("actor".to_string(), AttributeValue::S(self.actor.clone()));
+
+        if let Some(ref previous_status) = self.previous_status {
+            item.insert("previous_status".to_string(), AttributeValue::S(previous_status.to_string()));
+        }
+
+        if let Some(ref reason) = self.reason {
+            item.insert("reason".to_string(), AttributeValue::S(reason.clone()));
+        }
+
+        if let Some(ref changed_fields) = self.changed_fields {
+            item.insert("changed_fields".to_string(), AttributeValue::S(changed_fields.clone()));
+        }
+
+        item
+    }
+
+    /// Convert from DynamoDB item
+    pub fn from_dynamodb_item(item: HashMap<String, AttributeValue>) -> Option<Self> {
+        let transaction_id = item.get("transaction_id")?.as_s().ok()?;
+        let event_timestamp_str = item.get("event_timestamp")?.as_n().ok()?;
+        let new_status_str = item.get("new_status")?.as_s().ok()?;
+        let actor = item.get("actor")?.as_s().ok()?;
+
+        let event_timestamp_ms = event_timestamp_str.parse::<i64>().ok()?;
+        let event_timestamp = DateTime::from_timestamp(event_timestamp_ms / 1000, ((event_timestamp_ms % 1000) * 1_000_000) as u32)?;
+
+        let parse_status = |s: &str| match s {
+            "PENDING" => Some(TransactionStatus::Pending),
+            "FUNDED" => Some(TransactionStatus::Funded),
+            "CONVERTED" => Some(TransactionStatus::Converted),
+            "TRANSFERRED" => Some(TransactionStatus::Transferred),
+            "COMPLETED" => Some(TransactionStatus::Completed),
+            "FAILED" => Some(TransactionStatus::Failed),
+            _ => None,
+        };
+
+        let new_status = parse_status(new_status_str)?;
+        let previous_status = item.get("previous_status")
+            .and_then(|av| av.as_s().ok())
+            .and_then(|s| parse_status(s));
+
+        let reason = item.get("reason").and_then(|av| av.as_s().ok()).map(|s| s.to_string());
+        let changed_fields = item.get("changed_fields").and_then(|av| av.as_s().ok()).map(|s| s.to_string());
+
+        Some(TransactionEvent {
+            transaction_id: transaction_id.to_string(),
+            event_timestamp,
+            previous_status,
+            new_status,
+            actor: actor.to_string(),
+            reason,
+            changed_fields,
+        })
+    }
+}
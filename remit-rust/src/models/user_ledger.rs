@@ -0,0 +1,122 @@
+use aws_sdk_dynamodb::model::AttributeValue;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// What moved a user's prepaid balance. Distinct from `LedgerEntry::account`, which tags the
+/// double-entry side of a transfer's own internal bookkeeping rather than a user-facing reason.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum UserLedgerEntryKind {
+    Deposit,
+    Remittance,
+    Fee,
+    Refund,
+}
+
+impl ToString for UserLedgerEntryKind {
+    fn to_string(&self) -> String {
+        match self {
+            UserLedgerEntryKind::Deposit => "DEPOSIT".to_string(),
+            UserLedgerEntryKind::Remittance => "REMITTANCE".to_string(),
+            UserLedgerEntryKind::Fee => "FEE".to_string(),
+            UserLedgerEntryKind::Refund => "REFUND".to_string(),
+        }
+    }
+}
+
+impl UserLedgerEntryKind {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "DEPOSIT" => Some(UserLedgerEntryKind::Deposit),
+            "REMITTANCE" => Some(UserLedgerEntryKind::Remittance),
+            "FEE" => Some(UserLedgerEntryKind::Fee),
+            "REFUND" => Some(UserLedgerEntryKind::Refund),
+            _ => None,
+        }
+    }
+}
+
+/// A single movement of a user's prepaid balance, written atomically with the balance update
+/// that produced it so the balance and its history can never diverge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserLedgerEntry {
+    pub entry_id: String,
+    pub user_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub currency: String,
+
+    /// Positive for a credit (deposit, refund), negative for a debit (remittance, fee)
+    pub amount: Decimal,
+
+    pub kind: UserLedgerEntryKind,
+
+    /// `Transaction::transaction_id` this movement is attributed to, when there is one
+    pub reference_transaction_id: Option<String>,
+}
+
+impl UserLedgerEntry {
+    /// Create a new user ledger entry
+    pub fn new(
+        user_id: String,
+        currency: String,
+        amount: Decimal,
+        kind: UserLedgerEntryKind,
+        reference_transaction_id: Option<String>,
+    ) -> Self {
+        UserLedgerEntry {
+            entry_id: Uuid::new_v4().to_string(),
+            user_id,
+            timestamp: Utc::now(),
+            currency,
+            amount,
+            kind,
+            reference_transaction_id,
+        }
+    }
+
+    /// Convert to DynamoDB item
+    pub fn to_dynamodb_item(&self) -> HashMap<String, AttributeValue> {
+        let mut item = HashMap::new();
+
+        item.insert("entry_id".to_string(), AttributeValue::S(self.entry_id.clone()));
+        item.insert("user_id".to_string(), AttributeValue::S(self.user_id.clone()));
+        item.insert("timestamp".to_string(), AttributeValue::N(self.timestamp.timestamp().to_string()));
+        item.insert("currency".to_string(), AttributeValue::S(self.currency.clone()));
+        item.insert("amount".to_string(), AttributeValue::N(self.amount.to_string()));
+        item.insert("kind".to_string(), AttributeValue::S(self.kind.to_string()));
+
+        if let Some(ref reference_transaction_id) = self.reference_transaction_id {
+            item.insert("reference_transaction_id".to_string(), AttributeValue::S(reference_transaction_id.clone()));
+        }
+
+        item
+    }
+
+    /// Convert from DynamoDB item
+    pub fn from_dynamodb_item(item: HashMap<String, AttributeValue>) -> Option<Self> {
+        let entry_id = item.get("entry_id")?.as_s().ok()?;
+        let user_id = item.get("user_id")?.as_s().ok()?;
+        let timestamp_ts = item.get("timestamp")?.as_n().ok()?.parse::<i64>().ok()?;
+        let timestamp = DateTime::from_timestamp(timestamp_ts, 0)?;
+        let currency = item.get("currency")?.as_s().ok()?;
+        let amount = item.get("amount")?.as_n().ok()?.parse::<Decimal>().ok()?;
+        let kind = UserLedgerEntryKind::from_str(item.get("kind")?.as_s().ok()?)?;
+
+        let reference_transaction_id = item.get("reference_transaction_id")
+            .and_then(|av| av.as_s().ok())
+            .map(|s| s.to_string());
+
+        Some(UserLedgerEntry {
+            entry_id: entry_id.to_string(),
+            user_id: user_id.to_string(),
+            timestamp,
+            currency: currency.to_string(),
+            amount,
+            kind,
+            reference_transaction_id,
+        })
+    }
+}
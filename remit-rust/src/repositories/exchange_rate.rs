@@ -1,5 +1,5 @@
 use aws_sdk_dynamodb::{types::AttributeValue, Client as DynamoDbClient};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 use crate::config::get_config;
 use crate::errors::{AppError, AppResult};
@@ -101,4 +101,43 @@ impl ExchangeRateRepository {
             
         Ok(exchange_rates)
     }
+
+    /// Look up the exchange rate that was live at a specific point in time, for reconciling
+    /// or auditing a transaction against the exact quote it was converted at.
+    pub async fn get_rate_at(
+        &self,
+        source_currency: &str,
+        destination_currency: &str,
+        at: DateTime<Utc>,
+    ) -> AppResult<Option<ExchangeRate>> {
+        let currency_pair = format!("{}_{}", source_currency, destination_currency);
+
+        // Query the (currency_pair, timestamp) index directly so the "at or before" bound is
+        // part of the key condition. A filter_expression is applied *after* Limit, so pairing
+        // `.limit(1)` with a filter on timestamp only ever inspects the single newest item and
+        // returns None if that item happens to be newer than `at`.
+        let result = self.client.query()
+            .table_name(&self.table_name)
+            .index_name("CurrencyPairTimestampIndex")
+            .key_condition_expression("currency_pair = :currency_pair AND #timestamp <= :at")
+            .expression_attribute_names("#timestamp", "timestamp")
+            .expression_attribute_values(":currency_pair", AttributeValue::S(currency_pair))
+            .expression_attribute_values(":at", AttributeValue::N(at.timestamp().to_string()))
+            .limit(1)
+            .scan_index_forward(false)  // Sort newest first
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query exchange rate history: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+
+        if items.is_empty() {
+            return Ok(None);
+        }
+
+        let exchange_rate = ExchangeRate::from_dynamodb_item(items[0].clone())
+            .ok_or_else(|| AppError::database_error("Failed to parse exchange rate from DynamoDB item".to_string()))?;
+
+        Ok(Some(exchange_rate))
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,116 @@
+use aws_sdk_dynamodb::{types::AttributeValue, Client as DynamoDbClient};
+
+use crate::config::get_config;
+use crate::errors::{AppError, AppResult};
+use crate::models::{IdempotencyRecord, IdempotencyStatus};
+
+/// Outcome of claiming an `Idempotency-Key` against whatever previous attempt may already hold it
+pub enum IdempotencyOutcome {
+    /// First use of this key: the caller should run its handler and call `complete`
+    Started,
+    /// The same key and request body already completed; replay this response instead of
+    /// rerunning the handler
+    Completed { response_status: u16, response_body: String },
+    /// The same key is claimed by an attempt that hasn't finished yet
+    InProgress,
+    /// The same key was reused with a different request body
+    Conflict,
+}
+
+/// Repository backing `Idempotency-Key` deduplication for payment-creation endpoints
+pub struct IdempotencyRepository {
+    client: DynamoDbClient,
+    table_name: String,
+}
+
+impl IdempotencyRepository {
+    /// Create a new idempotency repository
+    pub fn new(client: DynamoDbClient) -> Self {
+        let config = get_config();
+
+        IdempotencyRepository {
+            client,
+            table_name: config.database.idempotency_table.clone(),
+        }
+    }
+
+    /// Atomically claim `key` for `request_hash`, or inspect whatever record already claimed it
+    pub async fn begin(&self, key: &str, request_hash: &str) -> AppResult<IdempotencyOutcome> {
+        let record = IdempotencyRecord::new(key.to_string(), request_hash.to_string());
+
+        let result = self.client.put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(record.to_dynamodb_item()))
+            .condition_expression("attribute_not_exists(idempotency_key)")
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(IdempotencyOutcome::Started),
+            Err(e) => {
+                if !e.to_string().contains("ConditionalCheckFailedException") {
+                    return Err(AppError::database_error(format!("Failed to claim idempotency key: {}", e)));
+                }
+
+                let existing = self.get(key).await?
+                    .ok_or_else(|| AppError::database_error("Idempotency key conflict but no existing record found".to_string()))?;
+
+                if existing.request_hash != request_hash {
+                    return Ok(IdempotencyOutcome::Conflict);
+                }
+
+                match existing.status {
+                    IdempotencyStatus::InProgress => Ok(IdempotencyOutcome::InProgress),
+                    IdempotencyStatus::Completed => Ok(IdempotencyOutcome::Completed {
+                        response_status: existing.response_status.unwrap_or(200),
+                        response_body: existing.response_body.unwrap_or_default(),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Release a claimed key whose handler failed, deleting its record so a legitimate retry can
+    /// `begin` again instead of being rejected with `IdempotencyOutcome::InProgress` for the rest
+    /// of `idempotency_retention_days`.
+    pub async fn release(&self, key: &str) -> AppResult<()> {
+        self.client.delete_item()
+            .table_name(&self.table_name)
+            .key("idempotency_key", AttributeValue::S(key.to_string()))
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to release idempotency key: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Record the response for a completed idempotent request, so a retried call with the same
+    /// key replays it instead of rerunning the handler
+    pub async fn complete(&self, key: &str, response_status: u16, response_body: &str) -> AppResult<()> {
+        self.client.update_item()
+            .table_name(&self.table_name)
+            .key("idempotency_key", AttributeValue::S(key.to_string()))
+            .update_expression("SET #status = :status, response_status = :response_status, response_body = :response_body")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":status", AttributeValue::S(IdempotencyStatus::Completed.to_string()))
+            .expression_attribute_values(":response_status", AttributeValue::N(response_status.to_string()))
+            .expression_attribute_values(":response_body", AttributeValue::S(response_body.to_string()))
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to complete idempotency key: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Option<IdempotencyRecord>> {
+        let result = self.client.get_item()
+            .table_name(&self.table_name)
+            .key("idempotency_key", AttributeValue::S(key.to_string()))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to get idempotency record: {}", e)))?;
+
+        Ok(result.item.and_then(IdempotencyRecord::from_dynamodb_item))
+    }
+}
@@ -1,5 +1,15 @@
 pub mod transaction;
 pub mod exchange_rate;
+pub mod transaction_event;
+pub mod quote;
+pub mod processed_event;
+pub mod user_ledger;
+pub mod idempotency;
 
 pub use transaction::TransactionRepository;
-pub use exchange_rate::ExchangeRateRepository; 
\ No newline at end of file
+pub use exchange_rate::ExchangeRateRepository;
+pub use transaction_event::TransactionEventRepository;
+pub use quote::QuoteRepository;
+pub use processed_event::{ProcessedEventFilter, ProcessedEventRepository};
+pub use user_ledger::UserLedgerRepository;
+pub use idempotency::{IdempotencyOutcome, IdempotencyRepository}; 
\ No newline at end of file
@@ -0,0 +1,146 @@
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client as DynamoDbClient;
+use bloomfilter::Bloom;
+use chrono::{Duration, Utc};
+use std::sync::RwLock;
+
+use crate::config::get_config;
+use crate::errors::{AppError, AppResult};
+use crate::models::ProcessedEvent;
+
+/// Repository backing webhook delivery dedupe in DynamoDB
+pub struct ProcessedEventRepository {
+    client: DynamoDbClient,
+    table_name: String,
+}
+
+impl ProcessedEventRepository {
+    /// Create a new processed-event repository
+    pub fn new(client: DynamoDbClient) -> Self {
+        let config = get_config();
+
+        ProcessedEventRepository {
+            client,
+            table_name: config.database.processed_events_table.clone(),
+        }
+    }
+
+    /// Atomically claim `event_key` as processed. Returns `true` if this call performed the
+    /// claim (first delivery), `false` if the key was already claimed (duplicate delivery) so
+    /// the caller should skip re-applying its side effects.
+    pub async fn try_claim(&self, event_key: &str) -> AppResult<bool> {
+        let event = ProcessedEvent::new(event_key.to_string());
+
+        let result = self.client.put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(event.to_dynamodb_item()))
+            .condition_expression("attribute_not_exists(event_key)")
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                if e.to_string().contains("ConditionalCheckFailedException") {
+                    Ok(false)
+                } else {
+                    Err(AppError::database_error(format!("Failed to claim processed event: {}", e)))
+                }
+            }
+        }
+    }
+
+    /// Authoritative, read-only check for whether `event_key` has already been claimed.
+    /// Used to confirm a bloom filter's "maybe seen" verdict without attempting (and likely
+    /// failing) a conditional claim.
+    pub async fn exists(&self, event_key: &str) -> AppResult<bool> {
+        let result = self.client.get_item()
+            .table_name(&self.table_name)
+            .key("event_key", AttributeValue::S(event_key.to_string()))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to look up processed event: {}", e)))?;
+
+        Ok(result.item.is_some())
+    }
+
+    /// Scan for event keys claimed within the last `within_days`, so a freshly started process
+    /// can repopulate its in-memory bloom filter instead of starting it empty.
+    pub async fn list_recent(&self, within_days: i64) -> AppResult<Vec<ProcessedEvent>> {
+        let cutoff = (Utc::now() - Duration::days(within_days)).timestamp().to_string();
+
+        let result = self.client.scan()
+            .table_name(&self.table_name)
+            .filter_expression("processed_at >= :cutoff")
+            .expression_attribute_values(":cutoff", AttributeValue::N(cutoff))
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to scan processed events: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        Ok(items.into_iter().filter_map(ProcessedEvent::from_dynamodb_item).collect())
+    }
+}
+
+/// Fast in-memory pre-check in front of `ProcessedEventRepository`, so a webhook handler's
+/// common case (an event id never seen before) doesn't pay for a DynamoDB round trip before
+/// deciding to process it. A bloom filter can only say "definitely not seen" or "maybe seen";
+/// a "maybe seen" verdict falls back to `ProcessedEventRepository::exists` to confirm before
+/// skipping, and the final claim is always the authoritative conditional write, so false
+/// positives cost an extra read rather than a missed or duplicated side effect.
+pub struct ProcessedEventFilter {
+    repo: ProcessedEventRepository,
+    bloom: RwLock<Bloom<String>>,
+}
+
+impl ProcessedEventFilter {
+    /// Build a filter sized from `business_rules.webhook_bloom_filter_expected_items` /
+    /// `_false_positive_rate`. Call `rebuild` once at startup to repopulate it from recent
+    /// `ProcessedEventRepository` records; a freshly constructed filter otherwise starts empty
+    /// and simply falls back to DynamoDB until it warms up.
+    pub fn new(repo: ProcessedEventRepository) -> Self {
+        let config = get_config();
+        let bloom = Bloom::new_for_fp_rate(
+            config.business_rules.webhook_bloom_filter_expected_items as usize,
+            config.business_rules.webhook_bloom_filter_false_positive_rate,
+        );
+
+        ProcessedEventFilter {
+            repo,
+            bloom: RwLock::new(bloom),
+        }
+    }
+
+    /// Repopulate the bloom filter from event keys claimed within the processed-event
+    /// retention window, so a restart doesn't lose the fast path for recently-seen events.
+    pub async fn rebuild(&self) -> AppResult<()> {
+        let retention_days = get_config().business_rules.processed_event_retention_days as i64;
+        let recent = self.repo.list_recent(retention_days).await?;
+
+        let mut bloom = self.bloom.write().unwrap();
+        for event in recent {
+            bloom.set(&event.event_key);
+        }
+
+        Ok(())
+    }
+
+    /// Claim `event_key` as processed. Returns `true` if this call performed the claim (first
+    /// delivery), `false` if it was already claimed (duplicate delivery) so the caller should
+    /// skip re-applying its side effects.
+    pub async fn try_claim(&self, event_key: &str) -> AppResult<bool> {
+        let maybe_seen = self.bloom.read().unwrap().check(&event_key.to_string());
+
+        if maybe_seen && self.repo.exists(event_key).await? {
+            return Ok(false);
+        }
+
+        let claimed = self.repo.try_claim(event_key).await?;
+        if claimed {
+            self.bloom.write().unwrap().set(&event_key.to_string());
+        }
+
+        Ok(claimed)
+    }
+}
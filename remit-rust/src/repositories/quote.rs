@@ -0,0 +1,53 @@
+use aws_sdk_dynamodb::{types::AttributeValue, Client as DynamoDbClient};
+
+use crate::config::get_config;
+use crate::errors::{AppError, AppResult};
+use crate::models::Quote;
+
+/// Repository for locked-rate quote operations in DynamoDB
+pub struct QuoteRepository {
+    client: DynamoDbClient,
+    table_name: String,
+}
+
+impl QuoteRepository {
+    /// Create a new quote repository
+    pub fn new(client: DynamoDbClient) -> Self {
+        let config = get_config();
+
+        QuoteRepository {
+            client,
+            table_name: config.database.quotes_table.clone(),
+        }
+    }
+
+    /// Save a quote to DynamoDB
+    pub async fn save(&self, quote: &Quote) -> AppResult<()> {
+        let item = quote.to_dynamodb_item();
+
+        self.client.put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to save quote: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Get a quote by ID
+    pub async fn get_by_id(&self, quote_id: &str) -> AppResult<Option<Quote>> {
+        let result = self.client.get_item()
+            .table_name(&self.table_name)
+            .key("quote_id", AttributeValue::S(quote_id.to_string()))
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to get quote: {}", e)))?;
+
+        match result.item {
+            Some(item) => Ok(Some(Quote::from_dynamodb_item(item)
+                .ok_or_else(|| AppError::database_error("Failed to parse quote from DynamoDB item".to_string()))?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -1,28 +1,114 @@
-use aws_sdk_dynamodb::{types::AttributeValue, Client as DynamoDbClient};
+use aws_sdk_dynamodb::{
+    types::AttributeValue,
+    model::{Put, TransactWriteItem, Update},
+    Client as DynamoDbClient,
+};
 use chrono::Utc;
 use std::collections::HashMap;
 
 use crate::config::get_config;
 use crate::errors::{AppError, AppResult};
-use crate::models::{Transaction, TransactionStatus};
+use crate::models::{allowed_prev_statuses, LedgerEntry, Page, Transaction, TransactionEvent, TransactionStatus};
+
+/// Map a DynamoDB update/put error, recognizing `ConditionalCheckFailedException`
+/// as a version/state conflict rather than an opaque database error.
+fn map_condition_error<E: std::fmt::Display>(context: &str, err: E) -> AppError {
+    let message = err.to_string();
+    if message.contains("ConditionalCheckFailedException") {
+        AppError::conflict(format!("{}: version mismatch or invalid transition", context))
+    } else {
+        AppError::database_error(format!("{}: {}", context, message))
+    }
+}
+
+/// Encode a DynamoDB `LastEvaluatedKey` as an opaque base64 JSON cursor
+fn encode_cursor(key: &HashMap<String, AttributeValue>) -> AppResult<String> {
+    let mut plain = HashMap::new();
+    for (k, v) in key {
+        if let Ok(s) = v.as_s() {
+            plain.insert(k.clone(), serde_json::json!({ "S": s }));
+        } else if let Ok(n) = v.as_n() {
+            plain.insert(k.clone(), serde_json::json!({ "N": n }));
+        }
+    }
+
+    let json = serde_json::to_string(&plain)
+        .map_err(|e| AppError::internal_error(format!("Failed to encode cursor: {}", e)))?;
+    Ok(base64::encode(json))
+}
+
+/// Decode an opaque cursor back into a DynamoDB `ExclusiveStartKey`
+fn decode_cursor(cursor: &str) -> AppResult<HashMap<String, AttributeValue>> {
+    let json = base64::decode(cursor)
+        .map_err(|e| AppError::validation_error(format!("Invalid cursor: {}", e)))?;
+    let plain: HashMap<String, serde_json::Value> = serde_json::from_slice(&json)
+        .map_err(|e| AppError::validation_error(format!("Invalid cursor: {}", e)))?;
+
+    let mut key = HashMap::new();
+    for (k, v) in plain {
+        if let Some(s) = v.get("S").and_then(|x| x.as_str()) {
+            key.insert(k, AttributeValue::S(s.to_string()));
+        } else if let Some(n) = v.get("N").and_then(|x| x.as_str()) {
+            key.insert(k, AttributeValue::N(n.to_string()));
+        }
+    }
+    Ok(key)
+}
 
 /// Repository for transaction operations in DynamoDB
 pub struct TransactionRepository {
     client: DynamoDbClient,
     table_name: String,
+    ledger_table_name: String,
+    events_table_name: String,
 }
 
 impl TransactionRepository {
     /// Create a new transaction repository
     pub fn new(client: DynamoDbClient) -> Self {
         let config = get_config();
-        
+
         TransactionRepository {
             client,
             table_name: config.database.transactions_table.clone(),
+            ledger_table_name: config.database.ledger_entries_table.clone(),
+            events_table_name: config.database.transaction_events_table.clone(),
         }
     }
-    
+
+    /// Build the `TransactWriteItem` that appends an audit event, so a status change and its
+    /// log entry are always written together
+    fn event_transact_item(&self, event: &TransactionEvent) -> TransactWriteItem {
+        let put = Put::builder()
+            .table_name(&self.events_table_name)
+            .set_item(Some(event.to_dynamodb_item()))
+            .build();
+        TransactWriteItem::builder().put(put).build()
+    }
+
+    /// Atomically allocate the next monotonic `row_id`, used to order and page through a user's
+    /// history independent of wall-clock `created_at`. Backed by a `SET ... + 1` on a single counter
+    /// item (`transaction_id = "__row_id_sequence__"`) in the transactions table, which DynamoDB
+    /// guarantees is applied atomically even under concurrent callers.
+    pub async fn next_row_id(&self) -> AppResult<i64> {
+        let result = self.client.update_item()
+            .table_name(&self.table_name)
+            .key("transaction_id", AttributeValue::S("__row_id_sequence__".to_string()))
+            .update_expression("SET row_id = if_not_exists(row_id, :zero) + :one")
+            .expression_attribute_values(":zero", AttributeValue::N("0".to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .return_values("UPDATED_NEW")
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to allocate row_id: {}", e)))?;
+
+        result.attributes
+            .and_then(|attrs| attrs.get("row_id").and_then(|av| av.as_n().ok().map(|s| s.to_string())))
+            .and_then(|s| s.parse::<i64>().ok())
+            .ok_or_else(|| AppError::database_error("Failed to parse allocated row_id".to_string()))
+    }
+
+
     /// Save a transaction to DynamoDB
     pub async fn save(&self, transaction: &Transaction) -> AppResult<()> {
         let item = transaction.to_dynamodb_item();
@@ -37,6 +123,119 @@ impl TransactionRepository {
         Ok(())
     }
     
+    /// Deterministic key for the lock item `create_idempotent` claims via a conditional put,
+    /// so two concurrent calls with the same `idempotency_key` can't both "win" the insert.
+    /// `transaction_id` is a freshly minted UUID on every call, so a condition on it would be
+    /// vacuously true regardless of which attribute it names; this key is the same on every
+    /// retry, which is what makes `attribute_not_exists` on it an actual guard.
+    fn idempotency_lock_key(idempotency_key: &str) -> String {
+        format!("idem#{}", idempotency_key)
+    }
+
+    /// Save a transaction, deduping retried creation requests by `idempotency_key`. Returns the
+    /// saved (or previously-saved) transaction together with whether this call actually inserted
+    /// it, so a caller that conditions a side effect (e.g. debiting a balance) on the insert
+    /// doesn't repeat it for a retried request.
+    ///
+    /// Claims a lock item keyed by `idempotency_lock_key(idempotency_key)` with a conditional put
+    /// guarded by `attribute_not_exists(transaction_id)`, in the same `transact_write_items` call
+    /// as the (unconditioned) transaction row, so the two writes commit atomically: the lock key
+    /// is stable across retries of the same idempotency key, unlike `transaction_id` itself. On a
+    /// conflict (a prior attempt already claimed the lock), looks up and returns the
+    /// previously-stored transaction instead of erroring.
+    pub async fn create_idempotent(&self, transaction: &Transaction, idempotency_key: &str) -> AppResult<(Transaction, bool)> {
+        let mut item = transaction.to_dynamodb_item();
+        item.insert("idempotency_key".to_string(), AttributeValue::S(idempotency_key.to_string()));
+
+        // Deliberately doesn't set `idempotency_key` on the lock item itself: that attribute is
+        // what `IdempotencyKeyIndex` projects on, and a second item carrying the same value would
+        // make `get_by_idempotency_key`'s `limit(1)` query non-deterministic about which of the
+        // two it returns.
+        let lock_put = Put::builder()
+            .table_name(&self.table_name)
+            .item("transaction_id", AttributeValue::S(Self::idempotency_lock_key(idempotency_key)))
+            .condition_expression("attribute_not_exists(transaction_id)")
+            .build();
+
+        let transaction_put = Put::builder()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .build();
+
+        let result = self.client.transact_write_items()
+            .set_transact_items(Some(vec![
+                TransactWriteItem::builder().put(lock_put).build(),
+                TransactWriteItem::builder().put(transaction_put).build(),
+            ]))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok((transaction.clone(), true)),
+            Err(e) => {
+                if e.to_string().contains("ConditionalCheckFailedException") {
+                    self.get_by_idempotency_key(idempotency_key).await?
+                        .map(|existing| (existing, false))
+                        .ok_or_else(|| AppError::database_error(
+                            "Idempotency key conflict but no existing transaction found".to_string()
+                        ))
+                } else {
+                    Err(AppError::database_error(format!("Failed to save transaction: {}", e)))
+                }
+            }
+        }
+    }
+
+    /// Look up a previously-created transaction by its client idempotency key
+    pub async fn get_by_idempotency_key(&self, idempotency_key: &str) -> AppResult<Option<Transaction>> {
+        let result = self.client.query()
+            .table_name(&self.table_name)
+            .index_name("IdempotencyKeyIndex")
+            .key_condition_expression("idempotency_key = :idempotency_key")
+            .expression_attribute_values(":idempotency_key", AttributeValue::S(idempotency_key.to_string()))
+            .limit(1)
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query transaction by idempotency key: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        Ok(items.into_iter().next().and_then(Transaction::from_dynamodb_item))
+    }
+
+    /// Look up a transaction by its UPI payment reference ID, via `PaymentReferenceIdIndex`,
+    /// instead of scanning all pending transactions and filtering in memory
+    pub async fn get_by_payment_reference_id(&self, reference_id: &str) -> AppResult<Option<Transaction>> {
+        let result = self.client.query()
+            .table_name(&self.table_name)
+            .index_name("PaymentReferenceIdIndex")
+            .key_condition_expression("payment_reference_id = :payment_reference_id")
+            .expression_attribute_values(":payment_reference_id", AttributeValue::S(reference_id.to_string()))
+            .limit(1)
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query transaction by payment reference id: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        Ok(items.into_iter().next().and_then(Transaction::from_dynamodb_item))
+    }
+
+    /// Look up a transaction by its Wise transfer ID, via `TransferIdIndex`, instead of
+    /// scanning all transferred transactions and filtering in memory
+    pub async fn get_by_transfer_id(&self, transfer_id: &str) -> AppResult<Option<Transaction>> {
+        let result = self.client.query()
+            .table_name(&self.table_name)
+            .index_name("TransferIdIndex")
+            .key_condition_expression("transfer_id = :transfer_id")
+            .expression_attribute_values(":transfer_id", AttributeValue::S(transfer_id.to_string()))
+            .limit(1)
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query transaction by transfer id: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        Ok(items.into_iter().next().and_then(Transaction::from_dynamodb_item))
+    }
+
     /// Get a transaction by ID
     pub async fn get_by_id(&self, transaction_id: &str) -> AppResult<Transaction> {
         let result = self.client.get_item()
@@ -78,6 +277,71 @@ impl TransactionRepository {
         Ok(transactions)
     }
     
+    /// Get a page of transactions by user ID, following on from an optional cursor
+    /// returned by a previous call
+    pub async fn get_by_user_id_page(&self, user_id: &str, limit: Option<i32>, cursor: Option<&str>) -> AppResult<Page<Transaction>> {
+        let limit = limit.unwrap_or(50).min(100);
+
+        let mut request = self.client.query()
+            .table_name(&self.table_name)
+            .index_name("UserIdIndex")
+            .key_condition_expression("user_id = :user_id")
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+            .limit(limit)
+            .scan_index_forward(false);  // Sort newest first
+
+        if let Some(cursor) = cursor {
+            request = request.set_exclusive_start_key(Some(decode_cursor(cursor)?));
+        }
+
+        let result = request
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query transactions: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        let transactions = items.into_iter().filter_map(Transaction::from_dynamodb_item).collect();
+        let next_cursor = match result.last_evaluated_key {
+            Some(key) => Some(encode_cursor(&key)?),
+            None => None,
+        };
+
+        Ok(Page { items: transactions, next_cursor })
+    }
+
+    /// Get a page of a user's transactions ordered by the monotonic `row_id`, via
+    /// `UserIdRowIdIndex`, for the cursor-paginated `/remittance/history` endpoint.
+    ///
+    /// `start` is the last `row_id` the caller saw, or `None` to start from either end. A
+    /// positive `delta` returns up to `delta` rows *after* `start` in ascending order; a negative
+    /// `delta` returns up to `|delta|` rows *before* `start` in descending order.
+    pub async fn get_by_user_id_since_row(&self, user_id: &str, start: Option<i64>, delta: i64) -> AppResult<Vec<Transaction>> {
+        let ascending = delta >= 0;
+        let limit = delta.unsigned_abs().min(100).max(1) as i32;
+
+        let mut key_condition = "user_id = :user_id".to_string();
+        let mut request = self.client.query()
+            .table_name(&self.table_name)
+            .index_name("UserIdRowIdIndex")
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+            .limit(limit)
+            .scan_index_forward(ascending);
+
+        if let Some(start) = start {
+            key_condition.push_str(if ascending { " AND row_id > :start" } else { " AND row_id < :start" });
+            request = request.expression_attribute_values(":start", AttributeValue::N(start.to_string()));
+        }
+
+        let result = request
+            .key_condition_expression(key_condition)
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query transaction history: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        Ok(items.into_iter().filter_map(Transaction::from_dynamodb_item).collect())
+    }
+
     /// Get transactions by status
     pub async fn get_by_status(&self, status: TransactionStatus, limit: Option<i32>) -> AppResult<Vec<Transaction>> {
         let limit = limit.unwrap_or(50).min(100);
@@ -100,45 +364,138 @@ impl TransactionRepository {
             
         Ok(transactions)
     }
-    
-    /// Update transaction status
-    pub async fn update_status(&self, transaction_id: &str, status: TransactionStatus) -> AppResult<Transaction> {
+
+    /// Get a page of transactions by status, following on from an optional cursor
+    /// returned by a previous call
+    pub async fn get_by_status_page(&self, status: TransactionStatus, limit: Option<i32>, cursor: Option<&str>) -> AppResult<Page<Transaction>> {
+        let limit = limit.unwrap_or(50).min(100);
+
+        let mut request = self.client.query()
+            .table_name(&self.table_name)
+            .index_name("StatusCreatedAtIndex")
+            .key_condition_expression("status = :status")
+            .expression_attribute_values(":status", AttributeValue::S(status.to_string()))
+            .limit(limit);
+
+        if let Some(cursor) = cursor {
+            request = request.set_exclusive_start_key(Some(decode_cursor(cursor)?));
+        }
+
+        let result = request
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query transactions: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        let transactions = items.into_iter().filter_map(Transaction::from_dynamodb_item).collect();
+        let next_cursor = match result.last_evaluated_key {
+            Some(key) => Some(encode_cursor(&key)?),
+            None => None,
+        };
+
+        Ok(Page { items: transactions, next_cursor })
+    }
+
+    /// Update transaction status, enforcing optimistic locking on `expected_version`
+    /// and that `status` is reachable from the transaction's current status.
+    ///
+    /// Appends an audit event for the transition in the same `transact_write_items` call
+    /// so the event log can never diverge from the main record.
+    pub async fn update_status(
+        &self,
+        transaction_id: &str,
+        status: TransactionStatus,
+        expected_version: i64,
+        previous_status: TransactionStatus,
+        actor: &str,
+    ) -> AppResult<Transaction> {
         let now = Utc::now().timestamp().to_string();
-        
-        let result = self.client.update_item()
+        let allowed_prev = allowed_prev_statuses(&status);
+        if allowed_prev.is_empty() {
+            return Err(AppError::invalid_state(previous_status.to_string(), status.to_string()));
+        }
+
+        let mut condition_expression = "attribute_exists(transaction_id) AND version = :expected_version".to_string();
+        let mut update = Update::builder()
             .table_name(&self.table_name)
             .key("transaction_id", AttributeValue::S(transaction_id.to_string()))
-            .update_expression("SET #status = :status, updated_at = :updated_at")
+            .update_expression("SET #status = :status, updated_at = :updated_at, version = version + :one")
             .expression_attribute_names("#status", "status")
             .expression_attribute_values(":status", AttributeValue::S(status.to_string()))
             .expression_attribute_values(":updated_at", AttributeValue::N(now))
-            .return_values("ALL_NEW")
+            .expression_attribute_values(":expected_version", AttributeValue::N(expected_version.to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()));
+
+        if !allowed_prev.is_empty() {
+            let mut placeholders = Vec::with_capacity(allowed_prev.len());
+            for (i, prev) in allowed_prev.iter().enumerate() {
+                let placeholder = format!(":prev{}", i);
+                update = update.expression_attribute_values(&placeholder, AttributeValue::S(prev.to_string()));
+                placeholders.push(placeholder);
+            }
+            condition_expression = format!("{} AND #status IN ({})", condition_expression, placeholders.join(", "));
+        }
+
+        let update = update.condition_expression(condition_expression).build();
+
+        let event = TransactionEvent::new(
+            transaction_id.to_string(),
+            Some(previous_status),
+            status,
+            actor.to_string(),
+            None,
+            None,
+        );
+
+        let transact_items = vec![
+            TransactWriteItem::builder().update(update).build(),
+            self.event_transact_item(&event),
+        ];
+
+        self.client.transact_write_items()
+            .set_transact_items(Some(transact_items))
             .send()
             .await
-            .map_err(|e| AppError::database_error(format!("Failed to update transaction status: {}", e)))?;
-            
-        if let Some(attributes) = result.attributes {
-            Transaction::from_dynamodb_item(attributes)
-                .ok_or_else(|| AppError::database_error("Failed to parse transaction from DynamoDB item".to_string()))
-        } else {
-            Err(AppError::not_found(format!("Transaction not found: {}", transaction_id)))
-        }
+            .map_err(|e| map_condition_error("Failed to update transaction status", e))?;
+
+        self.get_by_id(transaction_id).await
     }
     
-    /// Update payment details
-    pub async fn update_payment_details(&self, transaction_id: &str, payment_details_json: &str) -> AppResult<Transaction> {
+    /// Update payment details, enforcing optimistic locking on `expected_version`.
+    ///
+    /// `reference_id`, when present, is denormalized onto the top-level `payment_reference_id`
+    /// attribute so the UPI webhook can look the transaction up via `PaymentReferenceIdIndex`
+    /// instead of scanning by status.
+    pub async fn update_payment_details(
+        &self,
+        transaction_id: &str,
+        payment_details_json: &str,
+        reference_id: Option<&str>,
+        expected_version: i64,
+    ) -> AppResult<Transaction> {
         let now = Utc::now().timestamp().to_string();
-        
-        let result = self.client.update_item()
+
+        let mut update_expression = "SET payment_details = :payment_details, updated_at = :updated_at, version = version + :one".to_string();
+        let mut request = self.client.update_item()
             .table_name(&self.table_name)
             .key("transaction_id", AttributeValue::S(transaction_id.to_string()))
-            .update_expression("SET payment_details = :payment_details, updated_at = :updated_at")
+            .condition_expression("attribute_exists(transaction_id) AND version = :expected_version")
             .expression_attribute_values(":payment_details", AttributeValue::S(payment_details_json.to_string()))
             .expression_attribute_values(":updated_at", AttributeValue::N(now))
-            .return_values("ALL_NEW")
+            .expression_attribute_values(":expected_version", AttributeValue::N(expected_version.to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .return_values("ALL_NEW");
+
+        if let Some(reference_id) = reference_id {
+            update_expression.push_str(", payment_reference_id = :payment_reference_id");
+            request = request.expression_attribute_values(":payment_reference_id", AttributeValue::S(reference_id.to_string()));
+        }
+
+        let result = request
+            .update_expression(update_expression)
             .send()
             .await
-            .map_err(|e| AppError::database_error(format!("Failed to update payment details: {}", e)))?;
+            .map_err(|e| map_condition_error("Failed to update payment details", e))?;
             
         if let Some(attributes) = result.attributes {
             Transaction::from_dynamodb_item(attributes)
@@ -148,51 +505,71 @@ impl TransactionRepository {
         }
     }
     
-    /// Update conversion details and exchange rate
+    /// Update conversion details and exchange rate, enforcing optimistic locking on
+    /// `expected_version`, appending an audit event in the same write
     pub async fn update_conversion_details(
-        &self, 
-        transaction_id: &str, 
+        &self,
+        transaction_id: &str,
         conversion_details_json: &str,
         exchange_rate: &str,
         destination_amount: &str,
+        expected_version: i64,
     ) -> AppResult<Transaction> {
         let now = Utc::now().timestamp().to_string();
-        
-        let result = self.client.update_item()
+
+        let update = Update::builder()
             .table_name(&self.table_name)
             .key("transaction_id", AttributeValue::S(transaction_id.to_string()))
-            .update_expression("SET conversion_details = :conversion_details, exchange_rate = :exchange_rate, destination_amount = :destination_amount, updated_at = :updated_at")
+            .update_expression("SET conversion_details = :conversion_details, exchange_rate = :exchange_rate, destination_amount = :destination_amount, updated_at = :updated_at, version = version + :one")
+            .condition_expression("attribute_exists(transaction_id) AND version = :expected_version")
             .expression_attribute_values(":conversion_details", AttributeValue::S(conversion_details_json.to_string()))
             .expression_attribute_values(":exchange_rate", AttributeValue::N(exchange_rate.to_string()))
             .expression_attribute_values(":destination_amount", AttributeValue::N(destination_amount.to_string()))
             .expression_attribute_values(":updated_at", AttributeValue::N(now))
-            .return_values("ALL_NEW")
+            .expression_attribute_values(":expected_version", AttributeValue::N(expected_version.to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .build();
+
+        let event = TransactionEvent::new(
+            transaction_id.to_string(),
+            Some(TransactionStatus::Funded),
+            TransactionStatus::Funded,
+            "system".to_string(),
+            None,
+            Some(conversion_details_json.to_string()),
+        );
+
+        let transact_items = vec![
+            TransactWriteItem::builder().update(update).build(),
+            self.event_transact_item(&event),
+        ];
+
+        self.client.transact_write_items()
+            .set_transact_items(Some(transact_items))
             .send()
             .await
-            .map_err(|e| AppError::database_error(format!("Failed to update conversion details: {}", e)))?;
-            
-        if let Some(attributes) = result.attributes {
-            Transaction::from_dynamodb_item(attributes)
-                .ok_or_else(|| AppError::database_error("Failed to parse transaction from DynamoDB item".to_string()))
-        } else {
-            Err(AppError::not_found(format!("Transaction not found: {}", transaction_id)))
-        }
+            .map_err(|e| map_condition_error("Failed to update conversion details", e))?;
+
+        self.get_by_id(transaction_id).await
     }
     
-    /// Update transfer details
-    pub async fn update_transfer_details(&self, transaction_id: &str, transfer_details_json: &str) -> AppResult<Transaction> {
+    /// Update transfer details, enforcing optimistic locking on `expected_version`
+    pub async fn update_transfer_details(&self, transaction_id: &str, transfer_details_json: &str, expected_version: i64) -> AppResult<Transaction> {
         let now = Utc::now().timestamp().to_string();
-        
+
         let result = self.client.update_item()
             .table_name(&self.table_name)
             .key("transaction_id", AttributeValue::S(transaction_id.to_string()))
-            .update_expression("SET transfer_details = :transfer_details, updated_at = :updated_at")
+            .update_expression("SET transfer_details = :transfer_details, updated_at = :updated_at, version = version + :one")
+            .condition_expression("attribute_exists(transaction_id) AND version = :expected_version")
             .expression_attribute_values(":transfer_details", AttributeValue::S(transfer_details_json.to_string()))
             .expression_attribute_values(":updated_at", AttributeValue::N(now))
+            .expression_attribute_values(":expected_version", AttributeValue::N(expected_version.to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
             .return_values("ALL_NEW")
             .send()
             .await
-            .map_err(|e| AppError::database_error(format!("Failed to update transfer details: {}", e)))?;
+            .map_err(|e| map_condition_error("Failed to update transfer details", e))?;
             
         if let Some(attributes) = result.attributes {
             Transaction::from_dynamodb_item(attributes)
@@ -201,24 +578,123 @@ impl TransactionRepository {
             Err(AppError::not_found(format!("Transaction not found: {}", transaction_id)))
         }
     }
-    
-    /// Mark transaction as failed
-    pub async fn mark_as_failed(&self, transaction_id: &str, failure_reason: &str) -> AppResult<Transaction> {
+
+    /// Atomically mark a transaction `Transferred` and write its double-entry ledger rows.
+    ///
+    /// Uses `TransactWriteItems` so the transfer-details update and every ledger insert
+    /// either all commit or none do, guarded by the transaction still being `Converted`.
+    /// `transfer_id`, when present, is denormalized onto the top-level `transfer_id` attribute
+    /// so the Wise webhook can look the transaction up via `TransferIdIndex` instead of
+    /// scanning by status. `connector_name`, when present, records which `PayoutConnector`
+    /// created the transfer so `check_transfer_status` can dispatch back to it.
+    pub async fn commit_transfer(
+        &self,
+        transaction_id: &str,
+        transfer_details_json: &str,
+        transfer_id: Option<&str>,
+        connector_name: Option<&str>,
+        ledger_entries: Vec<LedgerEntry>,
+        expected_version: i64,
+    ) -> AppResult<Transaction> {
         let now = Utc::now().timestamp().to_string();
-        
-        let result = self.client.update_item()
+
+        let mut update_expression = "SET transfer_details = :transfer_details, #status = :status, updated_at = :updated_at, version = version + :one".to_string();
+        let mut update_builder = Update::builder()
             .table_name(&self.table_name)
             .key("transaction_id", AttributeValue::S(transaction_id.to_string()))
-            .update_expression("SET #status = :status, failure_reason = :failure_reason, updated_at = :updated_at")
+            .condition_expression("attribute_exists(transaction_id) AND version = :expected_version AND #status = :expected_status")
             .expression_attribute_names("#status", "status")
-            .expression_attribute_values(":status", AttributeValue::S(TransactionStatus::Failed.to_string()))
-            .expression_attribute_values(":failure_reason", AttributeValue::S(failure_reason.to_string()))
+            .expression_attribute_values(":transfer_details", AttributeValue::S(transfer_details_json.to_string()))
+            .expression_attribute_values(":status", AttributeValue::S(TransactionStatus::Transferred.to_string()))
+            .expression_attribute_values(":expected_status", AttributeValue::S(TransactionStatus::Converted.to_string()))
             .expression_attribute_values(":updated_at", AttributeValue::N(now))
+            .expression_attribute_values(":expected_version", AttributeValue::N(expected_version.to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()));
+
+        if let Some(transfer_id) = transfer_id {
+            update_expression.push_str(", transfer_id = :transfer_id");
+            update_builder = update_builder.expression_attribute_values(":transfer_id", AttributeValue::S(transfer_id.to_string()));
+        }
+
+        if let Some(connector_name) = connector_name {
+            update_expression.push_str(", connector_name = :connector_name");
+            update_builder = update_builder.expression_attribute_values(":connector_name", AttributeValue::S(connector_name.to_string()));
+        }
+
+        let update = update_builder.update_expression(update_expression).build();
+
+        let mut transact_items = vec![TransactWriteItem::builder().update(update).build()];
+
+        for entry in &ledger_entries {
+            let put = Put::builder()
+                .table_name(&self.ledger_table_name)
+                .set_item(Some(entry.to_dynamodb_item()))
+                .condition_expression("attribute_not_exists(entry_id)")
+                .build();
+            transact_items.push(TransactWriteItem::builder().put(put).build());
+        }
+
+        self.client.transact_write_items()
+            .set_transact_items(Some(transact_items))
+            .send()
+            .await
+            .map_err(|e| map_condition_error("Failed to commit transfer", e))?;
+
+        self.get_by_id(transaction_id).await
+    }
+
+    /// Find transactions parked in `status` whose `updated_at` is older than `older_than_secs`,
+    /// surfacing candidates for a background reconciliation job (crashed worker, provider
+    /// timeout, ...). `StatusCreatedAtIndex` is keyed on `status` alone, so staleness is
+    /// applied as a filter rather than a key condition.
+    pub async fn get_stale_transactions(&self, status: TransactionStatus, older_than_secs: i64, limit: Option<i32>) -> AppResult<Vec<Transaction>> {
+        let limit = limit.unwrap_or(50).min(100);
+        let cutoff = (Utc::now().timestamp() - older_than_secs).to_string();
+
+        let result = self.client.query()
+            .table_name(&self.table_name)
+            .index_name("StatusCreatedAtIndex")
+            .key_condition_expression("status = :status")
+            .filter_expression("updated_at < :cutoff")
+            .expression_attribute_values(":status", AttributeValue::S(status.to_string()))
+            .expression_attribute_values(":cutoff", AttributeValue::N(cutoff))
+            .limit(limit)
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query stale transactions: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        Ok(items.into_iter().filter_map(Transaction::from_dynamodb_item).collect())
+    }
+
+    /// Conditionally claim a stale transaction for retry processing, bumping `attempt_count`
+    /// and extending `lease_until` so only one reconciliation worker acts on it at a time.
+    /// The claim only succeeds if no lease is currently held or the previous lease expired.
+    pub async fn claim_for_retry(&self, transaction_id: &str, expected_version: i64, lease_seconds: i64) -> AppResult<Transaction> {
+        let now = Utc::now().timestamp();
+        let lease_until = (now + lease_seconds).to_string();
+
+        let result = self.client.update_item()
+            .table_name(&self.table_name)
+            .key("transaction_id", AttributeValue::S(transaction_id.to_string()))
+            .update_expression(
+                "SET lease_until = :lease_until, version = version + :one, \
+                 attempt_count = if_not_exists(attempt_count, :zero) + :one"
+            )
+            .condition_expression(
+                "attribute_exists(transaction_id) AND version = :expected_version \
+                 AND (attribute_not_exists(lease_until) OR lease_until < :now)"
+            )
+            .expression_attribute_values(":lease_until", AttributeValue::N(lease_until))
+            .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+            .expression_attribute_values(":zero", AttributeValue::N("0".to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(":expected_version", AttributeValue::N(expected_version.to_string()))
             .return_values("ALL_NEW")
             .send()
             .await
-            .map_err(|e| AppError::database_error(format!("Failed to mark transaction as failed: {}", e)))?;
-            
+            .map_err(|e| map_condition_error("Failed to claim transaction for retry", e))?;
+
         if let Some(attributes) = result.attributes {
             Transaction::from_dynamodb_item(attributes)
                 .ok_or_else(|| AppError::database_error("Failed to parse transaction from DynamoDB item".to_string()))
@@ -226,4 +702,50 @@ impl TransactionRepository {
             Err(AppError::not_found(format!("Transaction not found: {}", transaction_id)))
         }
     }
+
+    /// Mark transaction as failed, enforcing optimistic locking on `expected_version`
+    pub async fn mark_as_failed(
+        &self,
+        transaction_id: &str,
+        failure_reason: &str,
+        expected_version: i64,
+        previous_status: TransactionStatus,
+    ) -> AppResult<Transaction> {
+        let now = Utc::now().timestamp().to_string();
+
+        let update = Update::builder()
+            .table_name(&self.table_name)
+            .key("transaction_id", AttributeValue::S(transaction_id.to_string()))
+            .update_expression("SET #status = :status, failure_reason = :failure_reason, updated_at = :updated_at, version = version + :one")
+            .condition_expression("attribute_exists(transaction_id) AND version = :expected_version")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":status", AttributeValue::S(TransactionStatus::Failed.to_string()))
+            .expression_attribute_values(":failure_reason", AttributeValue::S(failure_reason.to_string()))
+            .expression_attribute_values(":updated_at", AttributeValue::N(now))
+            .expression_attribute_values(":expected_version", AttributeValue::N(expected_version.to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .build();
+
+        let event = TransactionEvent::new(
+            transaction_id.to_string(),
+            Some(previous_status),
+            TransactionStatus::Failed,
+            "system".to_string(),
+            Some(failure_reason.to_string()),
+            None,
+        );
+
+        let transact_items = vec![
+            TransactWriteItem::builder().update(update).build(),
+            self.event_transact_item(&event),
+        ];
+
+        self.client.transact_write_items()
+            .set_transact_items(Some(transact_items))
+            .send()
+            .await
+            .map_err(|e| map_condition_error("Failed to mark transaction as failed", e))?;
+
+        self.get_by_id(transaction_id).await
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,52 @@
+use aws_sdk_dynamodb::{types::AttributeValue, Client as DynamoDbClient};
+
+use crate::config::get_config;
+use crate::errors::{AppError, AppResult};
+use crate::models::TransactionEvent;
+
+/// Repository for the append-only transaction audit log
+pub struct TransactionEventRepository {
+    client: DynamoDbClient,
+    table_name: String,
+}
+
+impl TransactionEventRepository {
+    /// Create a new transaction event repository
+    pub fn new(client: DynamoDbClient) -> Self {
+        let config = get_config();
+
+        TransactionEventRepository {
+            client,
+            table_name: config.database.transaction_events_table.clone(),
+        }
+    }
+
+    /// Append an event directly (outside of a transact-write batch)
+    pub async fn save(&self, event: &TransactionEvent) -> AppResult<()> {
+        self.client.put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(event.to_dynamodb_item()))
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to save transaction event: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Get the full audit history for a transaction, oldest first
+    pub async fn get_history(&self, transaction_id: &str) -> AppResult<Vec<TransactionEvent>> {
+        let result = self.client.query()
+            .table_name(&self.table_name)
+            .key_condition_expression("transaction_id = :transaction_id")
+            .expression_attribute_values(":transaction_id", AttributeValue::S(transaction_id.to_string()))
+            .scan_index_forward(true)
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query transaction events: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        let events = items.into_iter().filter_map(TransactionEvent::from_dynamodb_item).collect();
+
+        Ok(events)
+    }
+}
@@ -0,0 +1,222 @@
+use aws_sdk_dynamodb::{
+    types::AttributeValue,
+    model::{Put, TransactWriteItem, Update},
+    Client as DynamoDbClient,
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+use crate::config::get_config;
+use crate::errors::{AppError, AppResult};
+use crate::models::{Page, UserLedgerEntry, UserLedgerEntryKind};
+
+/// Map a DynamoDB transact-write error, recognizing `ConditionalCheckFailedException` on the
+/// balance update as an insufficient-balance failure rather than an opaque database error.
+fn map_debit_error<E: std::fmt::Display>(user_id: &str, err: E) -> AppError {
+    let message = err.to_string();
+    if message.contains("ConditionalCheckFailedException") {
+        AppError::insufficient_balance(format!("Insufficient balance for user {}", user_id))
+    } else {
+        AppError::database_error(format!("Failed to debit ledger: {}", message))
+    }
+}
+
+/// Encode a DynamoDB `LastEvaluatedKey` as an opaque base64 JSON cursor
+fn encode_cursor(key: &HashMap<String, AttributeValue>) -> AppResult<String> {
+    let mut plain = HashMap::new();
+    for (k, v) in key {
+        if let Ok(s) = v.as_s() {
+            plain.insert(k.clone(), serde_json::json!({ "S": s }));
+        } else if let Ok(n) = v.as_n() {
+            plain.insert(k.clone(), serde_json::json!({ "N": n }));
+        }
+    }
+
+    let json = serde_json::to_string(&plain)
+        .map_err(|e| AppError::internal_error(format!("Failed to encode cursor: {}", e)))?;
+    Ok(base64::encode(json))
+}
+
+/// Decode an opaque cursor back into a DynamoDB `ExclusiveStartKey`
+fn decode_cursor(cursor: &str) -> AppResult<HashMap<String, AttributeValue>> {
+    let json = base64::decode(cursor)
+        .map_err(|e| AppError::validation_error(format!("Invalid cursor: {}", e)))?;
+    let plain: HashMap<String, serde_json::Value> = serde_json::from_slice(&json)
+        .map_err(|e| AppError::validation_error(format!("Invalid cursor: {}", e)))?;
+
+    let mut key = HashMap::new();
+    for (k, v) in plain {
+        if let Some(s) = v.get("S").and_then(|x| x.as_str()) {
+            key.insert(k, AttributeValue::S(s.to_string()));
+        } else if let Some(n) = v.get("N").and_then(|x| x.as_str()) {
+            key.insert(k, AttributeValue::N(n.to_string()));
+        }
+    }
+    Ok(key)
+}
+
+/// Repository for a user's prepaid-wallet balance and its movement history in DynamoDB.
+///
+/// Every balance change is written as a `TransactWriteItems` call that updates the running
+/// balance and appends the corresponding `UserLedgerEntry` atomically, so the balance and its
+/// audit trail can never diverge.
+pub struct UserLedgerRepository {
+    client: DynamoDbClient,
+    balances_table_name: String,
+    entries_table_name: String,
+}
+
+impl UserLedgerRepository {
+    /// Create a new user ledger repository
+    pub fn new(client: DynamoDbClient) -> Self {
+        let config = get_config();
+
+        UserLedgerRepository {
+            client,
+            balances_table_name: config.database.user_balances_table.clone(),
+            entries_table_name: config.database.user_ledger_entries_table.clone(),
+        }
+    }
+
+    /// Get a user's current balance, defaulting to zero for a user who has never had a
+    /// balance movement
+    pub async fn get_balance(&self, user_id: &str) -> AppResult<Decimal> {
+        let result = self.client.get_item()
+            .table_name(&self.balances_table_name)
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to get balance: {}", e)))?;
+
+        match result.item {
+            Some(item) => item.get("balance")
+                .and_then(|av| av.as_n().ok())
+                .and_then(|s| s.parse::<Decimal>().ok())
+                .ok_or_else(|| AppError::database_error("Failed to parse balance from DynamoDB item".to_string())),
+            None => Ok(Decimal::ZERO),
+        }
+    }
+
+    /// Credit a user's balance (deposit or refund), creating the balance row if this is their
+    /// first movement
+    pub async fn credit(
+        &self,
+        user_id: &str,
+        currency: &str,
+        amount: Decimal,
+        kind: UserLedgerEntryKind,
+        reference_transaction_id: Option<&str>,
+    ) -> AppResult<UserLedgerEntry> {
+        let entry = UserLedgerEntry::new(
+            user_id.to_string(),
+            currency.to_string(),
+            amount,
+            kind,
+            reference_transaction_id.map(|s| s.to_string()),
+        );
+
+        let update = Update::builder()
+            .table_name(&self.balances_table_name)
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .update_expression("SET balance = if_not_exists(balance, :zero) + :amount")
+            .expression_attribute_values(":zero", AttributeValue::N("0".to_string()))
+            .expression_attribute_values(":amount", AttributeValue::N(amount.to_string()))
+            .build();
+
+        let put = Put::builder()
+            .table_name(&self.entries_table_name)
+            .set_item(Some(entry.to_dynamodb_item()))
+            .condition_expression("attribute_not_exists(entry_id)")
+            .build();
+
+        let transact_items = vec![
+            TransactWriteItem::builder().update(update).build(),
+            TransactWriteItem::builder().put(put).build(),
+        ];
+
+        self.client.transact_write_items()
+            .set_transact_items(Some(transact_items))
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to credit ledger: {}", e)))?;
+
+        Ok(entry)
+    }
+
+    /// Debit a user's balance (remittance or fee), failing with `AppError::insufficient_balance`
+    /// if the balance doesn't cover `amount`
+    pub async fn debit(
+        &self,
+        user_id: &str,
+        currency: &str,
+        amount: Decimal,
+        kind: UserLedgerEntryKind,
+        reference_transaction_id: Option<&str>,
+    ) -> AppResult<UserLedgerEntry> {
+        let entry = UserLedgerEntry::new(
+            user_id.to_string(),
+            currency.to_string(),
+            -amount,
+            kind,
+            reference_transaction_id.map(|s| s.to_string()),
+        );
+
+        let update = Update::builder()
+            .table_name(&self.balances_table_name)
+            .key("user_id", AttributeValue::S(user_id.to_string()))
+            .update_expression("SET balance = balance - :amount")
+            .condition_expression("attribute_exists(balance) AND balance >= :amount")
+            .expression_attribute_values(":amount", AttributeValue::N(amount.to_string()))
+            .build();
+
+        let put = Put::builder()
+            .table_name(&self.entries_table_name)
+            .set_item(Some(entry.to_dynamodb_item()))
+            .condition_expression("attribute_not_exists(entry_id)")
+            .build();
+
+        let transact_items = vec![
+            TransactWriteItem::builder().update(update).build(),
+            TransactWriteItem::builder().put(put).build(),
+        ];
+
+        self.client.transact_write_items()
+            .set_transact_items(Some(transact_items))
+            .send()
+            .await
+            .map_err(|e| map_debit_error(user_id, e))?;
+
+        Ok(entry)
+    }
+
+    /// Get a page of a user's balance movement history, newest first, via `UserIdTimestampIndex`
+    pub async fn get_history_page(&self, user_id: &str, limit: Option<i32>, cursor: Option<&str>) -> AppResult<Page<UserLedgerEntry>> {
+        let limit = limit.unwrap_or(50).min(100);
+
+        let mut request = self.client.query()
+            .table_name(&self.entries_table_name)
+            .index_name("UserIdTimestampIndex")
+            .key_condition_expression("user_id = :user_id")
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+            .limit(limit)
+            .scan_index_forward(false);  // Sort newest first
+
+        if let Some(cursor) = cursor {
+            request = request.set_exclusive_start_key(Some(decode_cursor(cursor)?));
+        }
+
+        let result = request
+            .send()
+            .await
+            .map_err(|e| AppError::database_error(format!("Failed to query ledger history: {}", e)))?;
+
+        let items = result.items.unwrap_or_default();
+        let entries = items.into_iter().filter_map(UserLedgerEntry::from_dynamodb_item).collect();
+        let next_cursor = match result.last_evaluated_key {
+            Some(key) => Some(encode_cursor(&key)?),
+            None => None,
+        };
+
+        Ok(Page { items: entries, next_cursor })
+    }
+}
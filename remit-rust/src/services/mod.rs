@@ -0,0 +1,5 @@
+pub mod remittance;
+pub mod reconciliation;
+
+pub use remittance::RemittanceService;
+pub use reconciliation::{ReconciliationOutcome, ReconciliationService, ReconciliationWorker, SettlementEntry};
@@ -0,0 +1,300 @@
+use std::time::Duration;
+
+use bloomfilter::Bloom;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::time;
+
+use crate::config::get_config;
+use crate::errors::AppResult;
+use crate::models::{Transaction, TransactionStatus};
+use crate::repositories::TransactionRepository;
+
+use super::RemittanceService;
+
+/// Non-terminal states `process_payment`'s synchronous chain can strand a transaction in if
+/// the process crashes mid-flow.
+const NON_TERMINAL_STATUSES: [TransactionStatus; 3] = [
+    TransactionStatus::Funded,
+    TransactionStatus::Converted,
+    TransactionStatus::Transferred,
+];
+
+/// Background worker, spawned as a tokio task at startup, that periodically finds transactions
+/// stranded mid-flow and re-drives them through the next step of the remittance pipeline.
+///
+/// Each step it re-invokes (`process_currency_conversion`, `process_transfer`,
+/// `check_transfer_status`) is naturally idempotent: it re-checks the transaction's current
+/// status before acting, so a step that already completed (or partially ran before a crash) is
+/// safely skipped or resumed rather than double-applied. `Transferred` transactions in
+/// particular are polled via `check_transfer_status`, which calls the payout connector's
+/// `check_status` and only advances the transaction if the provider actually reports a terminal
+/// outcome — a transfer still in flight is left alone and picked up again next sweep, backed off
+/// the same way as every other non-terminal status, and one a completion webhook already landed
+/// for is left untouched since its status is no longer `Transferred` by the time we look. A
+/// `lease_until` claim (see `TransactionRepository::claim_for_retry`) ensures only one worker
+/// drives a given transaction at a time, and transactions that exceed
+/// `reconciliation_max_attempts` are marked `Failed` (dead-lettered) instead of being retried
+/// forever or polled indefinitely.
+pub struct ReconciliationWorker {
+    transaction_repo: TransactionRepository,
+    remittance_service: RemittanceService,
+    poll_interval: Duration,
+    stale_after_secs: i64,
+    max_attempts: i64,
+    lease_seconds: i64,
+}
+
+impl ReconciliationWorker {
+    /// Create a new reconciliation worker from the shared transaction repository and service
+    pub fn new(transaction_repo: TransactionRepository, remittance_service: RemittanceService) -> Self {
+        let config = get_config();
+
+        ReconciliationWorker {
+            transaction_repo,
+            remittance_service,
+            poll_interval: Duration::from_secs(config.business_rules.reconciliation_poll_seconds),
+            stale_after_secs: config.business_rules.reconciliation_stale_after_secs,
+            max_attempts: config.business_rules.reconciliation_max_attempts,
+            lease_seconds: config.business_rules.reconciliation_lease_seconds,
+        }
+    }
+
+    /// Run the reconciliation loop forever; intended to be handed to `tokio::spawn`
+    pub async fn run(self) {
+        let mut ticker = time::interval(self.poll_interval);
+        loop {
+            ticker.tick().await;
+
+            for status in NON_TERMINAL_STATUSES {
+                if let Err(e) = self.sweep(status).await {
+                    tracing::error!("Reconciliation sweep for {:?} failed: {}", status, e);
+                }
+            }
+        }
+    }
+
+    /// Find and advance transactions stuck in `status` for longer than `stale_after_secs`
+    async fn sweep(&self, status: TransactionStatus) -> AppResult<()> {
+        let stale = self.transaction_repo
+            .get_stale_transactions(status, self.stale_after_secs, Some(20))
+            .await?;
+
+        for transaction in stale {
+            if transaction.attempt_count >= self.max_attempts {
+                tracing::warn!(
+                    "Transaction {} exceeded {} reconciliation attempts, dead-lettering",
+                    transaction.transaction_id, self.max_attempts,
+                );
+                let _ = self.remittance_service.fail_transaction(
+                    &transaction,
+                    "Exceeded maximum reconciliation attempts",
+                    transaction.status.clone(),
+                ).await;
+                continue;
+            }
+
+            // Exponential backoff: each prior attempt doubles how stale the transaction must
+            // be before we touch it again.
+            let backoff_secs = self.stale_after_secs.saturating_mul(1i64 << transaction.attempt_count.min(10));
+            let age_secs = Utc::now().timestamp() - transaction.updated_at.timestamp();
+            if age_secs < backoff_secs {
+                continue;
+            }
+
+            let claimed = match self.transaction_repo
+                .claim_for_retry(&transaction.transaction_id, transaction.version, self.lease_seconds)
+                .await
+            {
+                Ok(claimed) => claimed,
+                // Lost the race to another worker, or the transaction moved on already; skip it this round.
+                Err(_) => continue,
+            };
+
+            self.advance(&claimed).await;
+        }
+
+        Ok(())
+    }
+
+    /// Re-invoke whichever pipeline step picks up where `transaction` left off
+    async fn advance(&self, transaction: &Transaction) {
+        match transaction.status {
+            TransactionStatus::Funded => {
+                if let Err(e) = self.remittance_service.process_currency_conversion(&transaction.transaction_id).await {
+                    tracing::warn!("Reconciliation step for transaction {} failed: {}", transaction.transaction_id, e);
+                }
+            }
+            TransactionStatus::Converted => {
+                if let Err(e) = self.remittance_service.process_transfer(&transaction.transaction_id).await {
+                    tracing::warn!("Reconciliation step for transaction {} failed: {}", transaction.transaction_id, e);
+                }
+            }
+            TransactionStatus::Transferred => {
+                // Poll the payout provider rather than assuming a transfer finished just because
+                // it's gone stale; `check_transfer_status` is a no-op once a webhook has already
+                // moved the transaction out of `Transferred`.
+                match self.remittance_service.check_transfer_status(&transaction.transaction_id).await {
+                    Ok(TransactionStatus::Transferred) => {
+                        tracing::debug!("Transfer for transaction {} still in flight, will re-poll", transaction.transaction_id);
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!("Reconciliation step for transaction {} failed: {}", transaction.transaction_id, e);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A single row from a bank/payout-provider settlement file: a reference to match against an
+/// outstanding transaction, and the status the provider reports for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementEntry {
+    /// A `transaction_id`, `payment_reference_id`, or `transfer_id` — whichever the provider
+    /// echoes back in its settlement file.
+    pub reference: String,
+    pub status: TransactionStatus,
+}
+
+/// Counts produced by `ReconciliationService::reconcile` for one settlement-file batch.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReconciliationOutcome {
+    /// Entries that passed the bloom filter and matched a genuinely outstanding transaction
+    pub matched: usize,
+    /// Entries whose matched transaction's status was successfully updated
+    pub updated: usize,
+    /// Entries filtered out with no I/O, whose bloom "maybe" turned out to be a false positive,
+    /// or whose status update was rejected
+    pub skipped: usize,
+}
+
+/// On-demand settlement reconciliation, distinct from the periodic `ReconciliationWorker`: this
+/// ingests an externally-supplied batch (a bank or payout provider's settlement file) rather than
+/// sweeping on a timer.
+///
+/// Most settlement rows reference a transaction that's already terminal or unknown to us, so
+/// checking every row against DynamoDB would mean one lookup per row for little payoff. Instead,
+/// `reconcile` first builds an in-memory bloom filter over the references of every currently
+/// `Pending`/`Transferred` transaction. A settlement entry whose reference the filter says is
+/// definitely absent is skipped with no I/O; only entries the filter flags as "maybe present"
+/// pay for a real repository fetch, which also discards the filter's false positives before any
+/// status is touched.
+pub struct ReconciliationService {
+    transaction_repo: TransactionRepository,
+}
+
+impl ReconciliationService {
+    /// Create a new reconciliation service from the shared transaction repository
+    pub fn new(transaction_repo: TransactionRepository) -> Self {
+        ReconciliationService { transaction_repo }
+    }
+
+    /// Page through every transaction in `status` via `get_by_status_page`, instead of
+    /// `get_by_status`, which silently clamps to 100 rows regardless of the requested limit.
+    async fn fetch_all_by_status(&self, status: TransactionStatus) -> AppResult<Vec<Transaction>> {
+        let mut all = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = self.transaction_repo.get_by_status_page(status.clone(), Some(100), cursor.as_deref()).await?;
+            all.extend(page.items);
+
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(all)
+    }
+
+    /// Build a bloom filter over every reference (`transaction_id`, `payment_reference_id`,
+    /// `transfer_id`) a `Pending` or `Transferred` transaction could be settled against
+    async fn build_outstanding_filter(&self) -> AppResult<Bloom<String>> {
+        let pending = self.fetch_all_by_status(TransactionStatus::Pending).await?;
+        let transferred = self.fetch_all_by_status(TransactionStatus::Transferred).await?;
+        let outstanding: Vec<Transaction> = pending.into_iter().chain(transferred.into_iter()).collect();
+
+        let false_positive_rate = get_config().business_rules.settlement_bloom_filter_false_positive_rate;
+        let mut bloom = Bloom::new_for_fp_rate(outstanding.len().max(1), false_positive_rate);
+
+        for transaction in &outstanding {
+            bloom.set(&transaction.transaction_id);
+            if let Some(ref reference_id) = transaction.payment_reference_id {
+                bloom.set(reference_id);
+            }
+            if let Some(ref transfer_id) = transaction.transfer_id {
+                bloom.set(transfer_id);
+            }
+        }
+
+        Ok(bloom)
+    }
+
+    /// Look up the transaction a settlement reference names, trying it as a `transaction_id`
+    /// first and falling back to the payment and transfer reference indexes
+    async fn lookup(&self, reference: &str) -> AppResult<Option<Transaction>> {
+        if let Ok(transaction) = self.transaction_repo.get_by_id(reference).await {
+            return Ok(Some(transaction));
+        }
+        if let Some(transaction) = self.transaction_repo.get_by_payment_reference_id(reference).await? {
+            return Ok(Some(transaction));
+        }
+        if let Some(transaction) = self.transaction_repo.get_by_transfer_id(reference).await? {
+            return Ok(Some(transaction));
+        }
+        Ok(None)
+    }
+
+    /// Reconcile a settlement-file batch against outstanding transactions, matching each entry's
+    /// reference via the bloom filter before doing any real lookup. Entries are applied
+    /// best-effort: a transaction an entry names that's already terminal, unknown, or rejects the
+    /// status transition is counted as skipped rather than failing the whole batch.
+    pub async fn reconcile(&self, entries: Vec<SettlementEntry>) -> AppResult<ReconciliationOutcome> {
+        let bloom = self.build_outstanding_filter().await?;
+        let mut outcome = ReconciliationOutcome::default();
+
+        for entry in entries {
+            if !bloom.check(&entry.reference) {
+                // Definitely no outstanding transaction carries this reference; no I/O needed.
+                outcome.skipped += 1;
+                continue;
+            }
+
+            let transaction = match self.lookup(&entry.reference).await? {
+                Some(transaction) if matches!(transaction.status, TransactionStatus::Pending | TransactionStatus::Transferred) => transaction,
+                // The filter said "maybe"; the authoritative fetch says otherwise.
+                _ => {
+                    outcome.skipped += 1;
+                    continue;
+                }
+            };
+
+            outcome.matched += 1;
+
+            let updated = self.transaction_repo.update_status(
+                &transaction.transaction_id,
+                entry.status,
+                transaction.version,
+                transaction.status.clone(),
+                "reconciliation",
+            ).await;
+
+            match updated {
+                Ok(_) => outcome.updated += 1,
+                Err(e) => {
+                    tracing::warn!(
+                        "Settlement entry for transaction {} rejected: {}",
+                        transaction.transaction_id, e,
+                    );
+                    outcome.skipped += 1;
+                }
+            }
+        }
+
+        Ok(outcome)
+    }
+}
@@ -1,27 +1,43 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
 use rust_decimal::Decimal;
 use serde_json::to_string;
+use tokio::sync::Notify;
+use tokio::time::timeout;
 use validator::Validate;
 
 use crate::config::get_config;
 use crate::errors::{AppError, AppResult};
 use crate::integrations::{
-    UserServiceClient, UpiClient, PaymentStatus,
-    AdBankClient, WiseClient, TransferStatus,
+    UserServiceClient, PaymentStatus, PaymentConnector,
+    AdBankClient, TransferStatus, PayoutConnector,
+    CurrencyProvider, build_payment_connector, build_payout_connectors, build_currency_provider,
+    RateSourceResolver, build_rate_source_resolver,
 };
 use crate::models::{
-    Transaction, TransactionStatus, BankAccountDetails,
-    PaymentDetails, ConversionDetails, TransferDetails,
+    Transaction, TransactionStatus, BankAccountDetails, ExchangeRate,
+    PaymentDetails, ConversionDetails, TransferDetails, LedgerEntry, Quote,
+    Page, UserLedgerEntry, UserLedgerEntryKind,
 };
-use crate::repositories::{TransactionRepository, ExchangeRateRepository};
+use crate::repositories::{TransactionRepository, ExchangeRateRepository, QuoteRepository, UserLedgerRepository};
 
 /// Service for handling the remittance flow
 pub struct RemittanceService {
     transaction_repo: TransactionRepository,
     exchange_rate_repo: ExchangeRateRepository,
+    quote_repo: QuoteRepository,
+    user_ledger_repo: UserLedgerRepository,
     user_service: UserServiceClient,
-    upi_client: UpiClient,
+    payment_connector: Box<dyn PaymentConnector>,
     ad_bank_client: AdBankClient,
-    wise_client: WiseClient,
+    currency_provider: Box<dyn CurrencyProvider>,
+    payout_connectors: Vec<Box<dyn PayoutConnector>>,
+    rate_source_resolver: RateSourceResolver,
+    /// Signaled by `create_transaction` on every insert so a long-polling `/remittance/history`
+    /// call waiting on a new row wakes up immediately instead of sitting out its full timeout.
+    new_transaction_notify: Arc<Notify>,
 }
 
 impl RemittanceService {
@@ -29,38 +45,156 @@ impl RemittanceService {
     pub fn new(
         transaction_repo: TransactionRepository,
         exchange_rate_repo: ExchangeRateRepository,
+        quote_repo: QuoteRepository,
+        user_ledger_repo: UserLedgerRepository,
     ) -> Self {
+        let config = get_config();
+        let ad_bank_client = AdBankClient::new();
+        let rate_source_resolver = build_rate_source_resolver(exchange_rate_repo.clone(), ad_bank_client.clone());
+
         RemittanceService {
             transaction_repo,
             exchange_rate_repo,
+            quote_repo,
+            user_ledger_repo,
             user_service: UserServiceClient::new(),
-            upi_client: UpiClient::new(),
-            ad_bank_client: AdBankClient::new(),
-            wise_client: WiseClient::new(),
+            payment_connector: build_payment_connector(&config.payment.connector),
+            ad_bank_client,
+            currency_provider: build_currency_provider(&config.currency.currency_provider),
+            payout_connectors: build_payout_connectors(&config.transfer.payout_connectors),
+            rate_source_resolver,
+            new_transaction_notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Look up a configured payout connector by the name stored on a transaction, falling back
+    /// to the first configured connector for transactions created before this field existed.
+    fn payout_connector_for(&self, connector_name: Option<&str>) -> &dyn PayoutConnector {
+        match connector_name {
+            Some(name) => self.payout_connectors.iter()
+                .find(|c| c.name() == name)
+                .unwrap_or(&self.payout_connectors[0])
+                .as_ref(),
+            None => self.payout_connectors[0].as_ref(),
+        }
+    }
+
+    /// Process a payment gateway webhook payload using the configured `PaymentConnector`
+    pub fn process_payment_webhook(&self, payload: serde_json::Value) -> AppResult<PaymentDetails> {
+        self.payment_connector.process_webhook(payload)
+    }
+
+    /// Route to the first configured connector that declares it can service `bank_details`
+    /// (e.g. `InteracClient` for a recipient with an email on file), falling through to the next
+    /// capable one on failure. If no connector claims capability, falls back to trying every
+    /// configured connector in order anyway, so a misconfigured capability table degrades to the
+    /// old try-everything behavior instead of failing outright.
+    async fn transfer_funds_with_fallback(
+        &self,
+        source_currency: &str,
+        source_amount: &str,
+        bank_details: &BankAccountDetails,
+        description: &str,
+    ) -> AppResult<(TransferDetails, &'static str)> {
+        let mut last_error = None;
+
+        // Capable connectors first, then the rest as a last-resort fallback, without retrying
+        // a capable connector a second time once it's already failed.
+        let capable = self.payout_connectors.iter().filter(|c| c.supports(bank_details));
+        let incapable = self.payout_connectors.iter().filter(|c| !c.supports(bank_details));
+
+        for connector in capable.chain(incapable) {
+            match connector.transfer_funds(source_currency, source_amount, bank_details, description).await {
+                Ok(transfer_details) => return Ok((transfer_details, connector.name())),
+                Err(e) => last_error = Some(e),
+            }
         }
+
+        Err(last_error.unwrap_or_else(|| AppError::internal_error("No payout connectors configured".to_string())))
     }
     
     /// Calculate fee for a remittance transaction
+    ///
+    /// Applies `fee_percentage` with a `min_fee_inr` floor, then caps the result at
+    /// `max_relative_fee_percentage` of `amount` so the flat minimum doesn't disproportionately
+    /// eat a small transfer.
     fn calculate_fee(&self, amount: Decimal) -> Decimal {
         let config = get_config();
         let fee_percentage = Decimal::from_f64(config.business_rules.fee_percentage).unwrap_or(Decimal::new(5, 1)); // Default 0.5%
         let min_fee = Decimal::from_u64(config.business_rules.min_fee_inr).unwrap_or(Decimal::new(100, 0)); // Default 100 INR
-        
+        let max_relative_fee_percentage = Decimal::from_f64(config.business_rules.max_relative_fee_percentage)
+            .unwrap_or(Decimal::new(10, 0)); // Default 10%
+
         let calculated_fee = amount * fee_percentage / Decimal::new(100, 0);
-        if calculated_fee < min_fee {
-            min_fee
-        } else {
-            calculated_fee
-        }
+        let fee = if calculated_fee < min_fee { min_fee } else { calculated_fee };
+
+        let max_fee = amount * max_relative_fee_percentage / Decimal::new(100, 0);
+        fee.min(max_fee)
+    }
+
+    /// Quote an exchange rate for a currency pair: the raw market rate and the customer-facing
+    /// effective rate after the platform spread is applied (`market_rate * (1 - spread/100)`,
+    /// the difference kept as platform margin).
+    pub async fn quote(&self, source_currency: &str, destination_currency: &str) -> AppResult<(Decimal, Decimal)> {
+        let (raw_rate, effective_rate, _provider) = self.quote_with_provider(source_currency, destination_currency).await?;
+        Ok((raw_rate, effective_rate))
+    }
+
+    /// Same as `quote`, but also returns the name of the `RateSource` the raw rate came from,
+    /// so a persisted `Quote` can record who priced it.
+    async fn quote_with_provider(&self, source_currency: &str, destination_currency: &str) -> AppResult<(Decimal, Decimal, String)> {
+        let config = get_config();
+        let exchange_rate = self.get_exchange_rate_with_provider(source_currency, destination_currency).await?;
+
+        // spread_percentage is e.g. 1.0 for a 1% markup; ExchangeRate::customer_rate takes the
+        // markup in basis points (1% = 100bps), so convert once here.
+        let markup_bps = (config.business_rules.spread_percentage * 100.0).round() as i32;
+        let effective_rate = exchange_rate.customer_rate(markup_bps);
+
+        Ok((exchange_rate.rate, effective_rate, exchange_rate.provider))
+    }
+
+    /// Create a locked-rate quote: fee, effective exchange rate, and destination amount computed
+    /// now and persisted with a short expiry, so a transaction created with this `quote_id` is
+    /// protected from rate moves between quoting and actual conversion.
+    pub async fn create_quote(
+        &self,
+        source_amount: Decimal,
+        source_currency: &str,
+        destination_currency: &str,
+    ) -> AppResult<Quote> {
+        let (_raw_rate, effective_rate, provider) = self.quote_with_provider(source_currency, destination_currency).await?;
+        let fee = self.calculate_fee(source_amount);
+        let net_amount = source_amount - fee;
+        let destination_amount = net_amount * effective_rate;
+
+        let quote = Quote::new(
+            source_amount,
+            source_currency.to_string(),
+            destination_currency.to_string(),
+            fee,
+            effective_rate,
+            destination_amount,
+            provider,
+        );
+
+        self.quote_repo.save(&quote).await?;
+
+        Ok(quote)
     }
     
     /// Create a new remittance transaction
+    ///
+    /// When `idempotency_key` is provided, a retried create with the same key returns the
+    /// transaction created by the original attempt instead of creating a duplicate.
     pub async fn create_transaction(
         &self,
         user_id: String,
         source_amount: Decimal,
         recipient_id: String,
         notes: Option<String>,
+        idempotency_key: Option<String>,
+        quote_id: Option<String>,
     ) -> AppResult<Transaction> {
         // Validate amount
         let config = get_config();
@@ -91,11 +225,35 @@ impl RemittanceService {
             account_number: recipient.account_number,
             account_holder_name: recipient.account_holder_name,
             ifsc_or_swift_code: recipient.ifsc_or_swift_code,
+            email: recipient.email,
         };
         
-        // Calculate fee
-        let fee = self.calculate_fee(source_amount);
-        
+        // If a quote_id was supplied, lock the fee to what was already quoted instead of
+        // recomputing it against the (possibly moved) current rate
+        let (fee, locked_quote_id) = match &quote_id {
+            Some(id) => {
+                let quote = self.quote_repo.get_by_id(id).await?
+                    .ok_or_else(|| AppError::not_found(format!("Quote not found: {}", id)))?;
+
+                if !quote.is_valid() {
+                    return Err(AppError::quote_expired(id.clone()));
+                }
+
+                if quote.source_amount != source_amount {
+                    return Err(AppError::validation_error(format!(
+                        "Quote {} was locked for a source amount of {}, but this request specifies {}",
+                        id, quote.source_amount, source_amount
+                    )));
+                }
+
+                (quote.fee, Some(quote.quote_id))
+            },
+            None => (self.calculate_fee(source_amount), None),
+        };
+
+        // Allocate this transaction's position in the per-user history cursor sequence
+        let row_id = self.transaction_repo.next_row_id().await?;
+
         // Create transaction
         let transaction = Transaction::new(
             user_id,
@@ -104,18 +262,84 @@ impl RemittanceService {
             bank_account_details,
             notes,
             fee,
+            idempotency_key.clone(),
+            locked_quote_id,
+            row_id,
         );
-        
+
         // Validate transaction
         transaction.validate()
             .map_err(|e| AppError::validation_error(format!("Invalid transaction: {}", e)))?;
-        
-        // Save transaction
-        self.transaction_repo.save(&transaction).await?;
-        
-        Ok(transaction)
+
+        // Save transaction first, deduping against a retried request with the same idempotency
+        // key, so a retry that lands after the original already succeeded observes the original
+        // row instead of minting (and funding) a second one.
+        let (saved, is_new) = match idempotency_key {
+            Some(key) => self.transaction_repo.create_idempotent(&transaction, &key).await?,
+            None => {
+                self.transaction_repo.save(&transaction).await?;
+                (transaction, true)
+            }
+        };
+
+        // Debit the prepaid balance for the full remit amount plus fee only for the attempt that
+        // actually created the row; a retry that deduped against an existing transaction must not
+        // debit the user a second time for it. If the debit fails (most commonly insufficient
+        // balance), mark the just-created transaction failed rather than leaving an un-debited
+        // Pending row behind.
+        if is_new {
+            if let Err(e) = self.user_ledger_repo.debit(
+                &saved.user_id,
+                &saved.source_currency,
+                source_amount + fee,
+                UserLedgerEntryKind::Remittance,
+                Some(&saved.transaction_id),
+            ).await {
+                self.transaction_repo.mark_as_failed(
+                    &saved.transaction_id,
+                    &format!("Failed to debit balance: {}", e),
+                    saved.version,
+                    TransactionStatus::Pending,
+                ).await?;
+                return Err(e);
+            }
+        }
+
+        // Wake any long-polling history readers waiting on a new row for this (or any) user
+        self.new_transaction_notify.notify_waiters();
+
+        Ok(saved)
     }
-    
+
+    /// Mark `transaction` failed and refund the prepaid balance `create_transaction` debited for
+    /// it, so a failure anywhere downstream of that debit (payment, conversion, or transfer)
+    /// doesn't permanently strand the customer's money. Every caller that fails a transaction
+    /// past the debit step should go through this instead of calling
+    /// `transaction_repo.mark_as_failed` directly.
+    pub async fn fail_transaction(
+        &self,
+        transaction: &Transaction,
+        reason: &str,
+        expected_status: TransactionStatus,
+    ) -> AppResult<Transaction> {
+        let failed = self.transaction_repo.mark_as_failed(
+            &transaction.transaction_id,
+            reason,
+            transaction.version,
+            expected_status,
+        ).await?;
+
+        self.user_ledger_repo.credit(
+            &transaction.user_id,
+            &transaction.source_currency,
+            transaction.source_amount + transaction.fees,
+            UserLedgerEntryKind::Refund,
+            Some(&transaction.transaction_id),
+        ).await?;
+
+        Ok(failed)
+    }
+
     /// Get transaction by ID
     pub async fn get_transaction(&self, transaction_id: &str) -> AppResult<Transaction> {
         self.transaction_repo.get_by_id(transaction_id).await
@@ -125,7 +349,48 @@ impl RemittanceService {
     pub async fn get_user_transactions(&self, user_id: &str, limit: Option<i32>) -> AppResult<Vec<Transaction>> {
         self.transaction_repo.get_by_user_id(user_id, limit).await
     }
-    
+
+    /// Cursor-paginated, long-polling history read: a positive `delta` returns up to `delta` rows
+    /// after `start` in ascending order, a negative `delta` returns up to `|delta|` rows before
+    /// `start` descending. When `delta` is positive and nothing matches yet, waits up to
+    /// `long_poll_ms` for `create_transaction` to signal a new row before giving up and
+    /// returning an empty page, rather than making the caller busy-poll.
+    pub async fn get_user_transaction_history(
+        &self,
+        user_id: &str,
+        start: Option<i64>,
+        delta: i64,
+        long_poll_ms: u64,
+    ) -> AppResult<Vec<Transaction>> {
+        // Register interest before the first read so a notify_waiters() racing with it isn't
+        // missed; Tokio delivers notify_waiters() to a `Notified` from the moment it's created,
+        // even before it's first polled.
+        let notified = self.new_transaction_notify.notified();
+
+        let rows = self.transaction_repo.get_by_user_id_since_row(user_id, start, delta).await?;
+        if !rows.is_empty() || delta <= 0 || long_poll_ms == 0 {
+            return Ok(rows);
+        }
+
+        // Wait for the next insert (anywhere) and re-check, rather than sleeping and polling;
+        // a single wakeup is usually enough since the window is narrow.
+        let _ = timeout(Duration::from_millis(long_poll_ms), notified).await;
+
+        self.transaction_repo.get_by_user_id_since_row(user_id, start, delta).await
+    }
+
+    /// Get a user's current prepaid balance together with a page of their deposit/debit history
+    pub async fn get_user_balance(&self, user_id: &str, limit: Option<i32>, cursor: Option<&str>) -> AppResult<(Decimal, Page<UserLedgerEntry>)> {
+        let balance = self.user_ledger_repo.get_balance(user_id).await?;
+        let history = self.user_ledger_repo.get_history_page(user_id, limit, cursor).await?;
+        Ok((balance, history))
+    }
+
+    /// Credit a user's prepaid balance, called when a deposit webhook confirms funds received
+    pub async fn credit_user_balance(&self, user_id: &str, currency: &str, amount: Decimal, reference_id: Option<&str>) -> AppResult<UserLedgerEntry> {
+        self.user_ledger_repo.credit(user_id, currency, amount, UserLedgerEntryKind::Deposit, reference_id).await
+    }
+
     /// Initiate payment for a transaction
     pub async fn initiate_payment(&self, transaction_id: &str) -> AppResult<String> {
         // Get transaction
@@ -149,8 +414,8 @@ impl RemittanceService {
             transaction.transaction_id
         );
         
-        // Initiate UPI payment
-        let payment_details = self.upi_client.create_payment(
+        // Initiate payment via the configured payment connector
+        let payment_details = self.payment_connector.create_payment(
             total_amount.to_string(),
             description,
         ).await?;
@@ -159,8 +424,10 @@ impl RemittanceService {
         let payment_details_json = to_string(&payment_details)
             .map_err(|e| AppError::internal_error(format!("Failed to serialize payment details: {}", e)))?;
             
-        self.transaction_repo.update_payment_details(transaction_id, &payment_details_json).await?;
-        
+        self.transaction_repo.update_payment_details(
+            transaction_id, &payment_details_json, payment_details.reference_id.as_deref(), transaction.version,
+        ).await?;
+
         // Return payment link for user to complete payment
         payment_details.payment_link.ok_or_else(|| AppError::internal_error("Payment link not available".to_string()))
     }
@@ -182,11 +449,19 @@ impl RemittanceService {
         let payment_details_json = to_string(&payment_details)
             .map_err(|e| AppError::internal_error(format!("Failed to serialize payment details: {}", e)))?;
             
-        let transaction = self.transaction_repo.update_payment_details(transaction_id, &payment_details_json).await?;
-        
+        let transaction = self.transaction_repo.update_payment_details(
+            transaction_id, &payment_details_json, payment_details.reference_id.as_deref(), transaction.version,
+        ).await?;
+
+        // Fail fast with a descriptive error before ever attempting the write if this would be
+        // an illegal transition or the required payment details aren't there yet
+        transaction.clone().transition_to(TransactionStatus::Funded)?;
+
         // Update transaction status to FUNDED
-        let transaction = self.transaction_repo.update_status(transaction_id, TransactionStatus::Funded).await?;
-        
+        let transaction = self.transaction_repo.update_status(
+            transaction_id, TransactionStatus::Funded, transaction.version, TransactionStatus::Pending, "system",
+        ).await?;
+
         // Trigger currency conversion (can be done asynchronously)
         // For now, we'll do it synchronously
         self.process_currency_conversion(transaction_id).await?;
@@ -208,12 +483,35 @@ impl RemittanceService {
             ));
         }
         
-        // Perform currency conversion via AD Bank
+        // Honor the locked-rate quote this transaction was created with, if it's still valid,
+        // instead of fetching a fresh rate that may have moved since the customer was quoted
         let source_amount = transaction.source_amount;
-        let (conversion_details, exchange_rate, destination_amount) = self.ad_bank_client
-            .convert_currency(&transaction.source_currency, &transaction.destination_currency, source_amount)
-            .await?;
-            
+        let locked_quote = match &transaction.quote_id {
+            Some(quote_id) => self.quote_repo.get_by_id(quote_id).await?.filter(Quote::is_valid),
+            None => None,
+        };
+
+        let (conversion_details, exchange_rate, destination_amount) = match locked_quote {
+            Some(quote) => {
+                // The quote only carries the already-marked-up rate; look up today's raw
+                // market rate purely for audit purposes (the applied rate stays the one locked
+                // at quote time regardless of where the market has since moved).
+                let market_rate = self.get_exchange_rate(&transaction.source_currency, &transaction.destination_currency).await.ok();
+
+                let conversion_details = ConversionDetails {
+                    conversion_id: Some(quote.quote_id.clone()),
+                    conversion_time: Some(Utc::now()),
+                    actual_exchange_rate: Some(quote.exchange_rate),
+                    market_exchange_rate: market_rate,
+                    reference_id: Some(quote.quote_id.clone()),
+                };
+                (conversion_details, quote.exchange_rate, quote.destination_amount)
+            },
+            None => self.currency_provider
+                .convert_currency(&transaction.source_currency, &transaction.destination_currency, source_amount)
+                .await?,
+        };
+
         // Update transaction with conversion details
         let conversion_details_json = to_string(&conversion_details)
             .map_err(|e| AppError::internal_error(format!("Failed to serialize conversion details: {}", e)))?;
@@ -223,10 +521,15 @@ impl RemittanceService {
             &conversion_details_json,
             &exchange_rate.to_string(),
             &destination_amount.to_string(),
+            transaction.version,
         ).await?;
-        
+
+        transaction.clone().transition_to(TransactionStatus::Converted)?;
+
         // Update transaction status to CONVERTED
-        let transaction = self.transaction_repo.update_status(transaction_id, TransactionStatus::Converted).await?;
+        let transaction = self.transaction_repo.update_status(
+            transaction_id, TransactionStatus::Converted, transaction.version, TransactionStatus::Funded, "system",
+        ).await?;
         
         // Trigger transfer (can be done asynchronously)
         // For now, we'll do it synchronously
@@ -256,8 +559,8 @@ impl RemittanceService {
             "Remittance".to_string()
         };
         
-        // Initiate transfer via Wise
-        let transfer_details = self.wise_client.transfer_funds(
+        // Initiate transfer, trying capable payout connectors first, then the rest, until one succeeds
+        let (transfer_details, connector_name) = self.transfer_funds_with_fallback(
             &transaction.source_currency,
             &transaction.destination_amount.unwrap_or_default().to_string(),
             &transaction.recipient_account_details,
@@ -267,11 +570,32 @@ impl RemittanceService {
         // Update transaction with transfer details
         let transfer_details_json = to_string(&transfer_details)
             .map_err(|e| AppError::internal_error(format!("Failed to serialize transfer details: {}", e)))?;
-            
-        self.transaction_repo.update_transfer_details(transaction_id, &transfer_details_json).await?;
-        
-        // Update transaction status to TRANSFERRED
-        let transaction = self.transaction_repo.update_status(transaction_id, TransactionStatus::Transferred).await?;
+
+        // Move the destination amount out of the remittance source and into the recipient
+        // account in the ledger, atomically with the status change
+        let ledger_entries = vec![
+            LedgerEntry::new(
+                transaction.transaction_id.clone(),
+                "remittance:source".to_string(),
+                transaction.destination_currency.clone(),
+                -transaction.destination_amount.unwrap_or_default(),
+            ),
+            LedgerEntry::new(
+                transaction.transaction_id.clone(),
+                format!("recipient:{}", transaction.recipient_id),
+                transaction.destination_currency.clone(),
+                transaction.destination_amount.unwrap_or_default(),
+            ),
+        ];
+
+        let transaction = self.transaction_repo.commit_transfer(
+            transaction_id,
+            &transfer_details_json,
+            transfer_details.transfer_id.as_deref(),
+            Some(connector_name),
+            ledger_entries,
+            transaction.version,
+        ).await?;
         
         // Return updated transaction
         Ok(transaction)
@@ -290,13 +614,17 @@ impl RemittanceService {
             ));
         }
         
+        transaction.clone().transition_to(TransactionStatus::Completed)?;
+
         // Update transaction status to COMPLETED
-        let transaction = self.transaction_repo.update_status(transaction_id, TransactionStatus::Completed).await?;
-        
+        let transaction = self.transaction_repo.update_status(
+            transaction_id, TransactionStatus::Completed, transaction.version, TransactionStatus::Transferred, "system",
+        ).await?;
+
         // Return updated transaction
         Ok(transaction)
     }
-    
+
     /// Check payment status manually
     pub async fn check_payment_status(&self, transaction_id: &str) -> AppResult<TransactionStatus> {
         // Get transaction
@@ -313,27 +641,29 @@ impl RemittanceService {
             .ok_or_else(|| AppError::internal_error("Payment ID not found".to_string()))?;
             
         // Check payment status
-        let payment_status = self.upi_client.check_status(&payment_id).await?;
+        let payment_status = self.payment_connector.check_status(&payment_id).await?;
         
         match payment_status {
             PaymentStatus::Completed => {
                 // Update transaction status to FUNDED
-                self.transaction_repo.update_status(transaction_id, TransactionStatus::Funded).await?;
-                
+                self.transaction_repo.update_status(
+                    transaction_id, TransactionStatus::Funded, transaction.version, TransactionStatus::Pending, "system",
+                ).await?;
+
                 // Trigger currency conversion (can be done asynchronously)
                 // For now, we'll do it synchronously
                 self.process_currency_conversion(transaction_id).await?;
-                
+
                 Ok(TransactionStatus::Funded)
             },
             PaymentStatus::Failed => {
-                // Mark transaction as failed
-                self.transaction_repo.mark_as_failed(transaction_id, "Payment failed").await?;
+                // Mark transaction as failed and refund the debited balance
+                self.fail_transaction(&transaction, "Payment failed", TransactionStatus::Pending).await?;
                 Ok(TransactionStatus::Failed)
             },
             PaymentStatus::Expired => {
-                // Mark transaction as failed
-                self.transaction_repo.mark_as_failed(transaction_id, "Payment expired").await?;
+                // Mark transaction as failed and refund the debited balance
+                self.fail_transaction(&transaction, "Payment expired", TransactionStatus::Pending).await?;
                 Ok(TransactionStatus::Failed)
             },
             _ => Ok(TransactionStatus::Pending),
@@ -355,62 +685,73 @@ impl RemittanceService {
             .clone()
             .ok_or_else(|| AppError::internal_error("Transfer ID not found".to_string()))?;
             
-        // Check transfer status
-        let transfer_status = self.wise_client.check_status(&transfer_id).await?;
+        // Check transfer status via the connector that created the transfer
+        let payout_connector = self.payout_connector_for(transaction.connector_name.as_deref());
+        let transfer_status = payout_connector.check_status(&transfer_id).await?;
         
         match transfer_status {
             TransferStatus::Completed => {
+                transaction.clone().transition_to(TransactionStatus::Completed)?;
+
                 // Update transaction status to COMPLETED
-                self.transaction_repo.update_status(transaction_id, TransactionStatus::Completed).await?;
+                self.transaction_repo.update_status(
+                    transaction_id, TransactionStatus::Completed, transaction.version, TransactionStatus::Transferred, "system",
+                ).await?;
                 Ok(TransactionStatus::Completed)
             },
             TransferStatus::Failed => {
-                // Mark transaction as failed
-                self.transaction_repo.mark_as_failed(transaction_id, "Transfer failed").await?;
+                // Mark transaction as failed and refund the debited balance
+                self.fail_transaction(&transaction, "Transfer failed", TransactionStatus::Transferred).await?;
                 Ok(TransactionStatus::Failed)
             },
             TransferStatus::Cancelled => {
-                // Mark transaction as failed
-                self.transaction_repo.mark_as_failed(transaction_id, "Transfer cancelled").await?;
+                // Mark transaction as failed and refund the debited balance
+                self.fail_transaction(&transaction, "Transfer cancelled", TransactionStatus::Transferred).await?;
                 Ok(TransactionStatus::Failed)
             },
             _ => Ok(TransactionStatus::Transferred),
         }
     }
     
-    /// Get current exchange rate
+    /// Get current exchange rate, resolved via the configured `RateSourceResolver`: the cached
+    /// rate if fresh, a live AD Bank quote if not, or a fixed fallback rate if neither is
+    /// available, so this never fails outright over a transient upstream outage.
     pub async fn get_exchange_rate(&self, source_currency: &str, destination_currency: &str) -> AppResult<Decimal> {
-        // First check if we have a cached rate
-        if let Ok(Some(rate)) = self.exchange_rate_repo.get_latest(source_currency, destination_currency).await {
-            return Ok(rate.rate);
+        Ok(self.get_exchange_rate_with_provider(source_currency, destination_currency).await?.rate)
+    }
+
+    /// Same as `get_exchange_rate`, but returns the full `ExchangeRate` (so e.g.
+    /// `quote_with_provider` can derive a customer rate from it via `ExchangeRate::customer_rate`),
+    /// not just its rate and provider name.
+    async fn get_exchange_rate_with_provider(&self, source_currency: &str, destination_currency: &str) -> AppResult<ExchangeRate> {
+        let exchange_rate = self.rate_source_resolver.get_rate(source_currency, destination_currency).await?;
+
+        // Cache a rate that didn't already come from the cache itself, so the next lookup
+        // can hit `DynamoCachedSource` instead of falling through again
+        if exchange_rate.provider != "dynamo_cached" {
+            self.exchange_rate_repo.save(&exchange_rate).await?;
         }
-        
-        // If not, fetch fresh rate from AD Bank
-        let exchange_rate = self.ad_bank_client.get_exchange_rate(source_currency, destination_currency).await?;
-        
-        // Save to repository
-        self.exchange_rate_repo.save(&exchange_rate).await?;
-        
-        Ok(exchange_rate.rate)
+
+        Ok(exchange_rate)
     }
     
     /// Calculate destination amount based on source amount and exchange rate
     pub async fn calculate_destination_amount(&self, source_amount: Decimal) -> AppResult<(Decimal, Decimal)> {
         let source_currency = "INR";
         let destination_currency = "CAD";
-        
-        // Get exchange rate
-        let exchange_rate = self.get_exchange_rate(source_currency, destination_currency).await?;
-        
+
+        // Quote the customer-facing effective rate (raw market rate less the platform spread)
+        let (_raw_rate, effective_rate) = self.quote(source_currency, destination_currency).await?;
+
         // Calculate fee
         let fee = self.calculate_fee(source_amount);
-        
+
         // Calculate net amount after fee
         let net_amount = source_amount - fee;
-        
+
         // Calculate destination amount
-        let destination_amount = net_amount * exchange_rate;
-        
-        Ok((destination_amount, exchange_rate))
+        let destination_amount = net_amount * effective_rate;
+
+        Ok((destination_amount, effective_rate))
     }
 } 
\ No newline at end of file